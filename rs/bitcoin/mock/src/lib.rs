@@ -1,8 +1,56 @@
-use ic_btc_interface::{Address, Utxo};
+use ic_btc_interface::{Address, Satoshi, Utxo};
 use serde::{Deserialize, Serialize};
 
 #[derive(candid::CandidType, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PushUtxoToAddress {
     pub address: Address,
     pub utxo: Utxo,
+    /// Whether to replace a UTXO with the same outpoint, at any address,
+    /// instead of rejecting the push. Defaults to false.
+    pub force: Option<bool>,
+}
+
+/// Aggregate information about the UTXOs of an address.
+///
+/// A UTXO is confirmed if its height is at most the current tip height, and
+/// pending otherwise.
+#[derive(candid::CandidType, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddressStats {
+    pub utxo_count: u64,
+    pub total_value: Satoshi,
+    pub confirmed_value: Satoshi,
+    pub pending_value: Satoshi,
+}
+
+/// The order in which the mock returns the UTXOs of an address.
+///
+/// UTXOs comparing equal under the selected order are returned by outpoint,
+/// so the result is deterministic for a given set of UTXOs.
+#[derive(
+    candid::CandidType, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub enum UtxoOrder {
+    /// By transaction ID, then by output index.
+    #[default]
+    OutPoint,
+    HeightAscending,
+    HeightDescending,
+    ValueAscending,
+    ValueDescending,
+}
+
+impl UtxoOrder {
+    /// Sorts the given UTXOs according to this order.
+    pub fn sort(&self, utxos: &mut [Utxo]) {
+        utxos.sort_by(|a, b| {
+            let primary = match self {
+                Self::OutPoint => std::cmp::Ordering::Equal,
+                Self::HeightAscending => a.height.cmp(&b.height),
+                Self::HeightDescending => b.height.cmp(&a.height),
+                Self::ValueAscending => a.value.cmp(&b.value),
+                Self::ValueDescending => b.value.cmp(&a.value),
+            };
+            primary.then_with(|| a.outpoint.cmp(&b.outpoint))
+        });
+    }
 }
@@ -1,15 +1,21 @@
 use candid::candid_method;
+use ic_bitcoin_canister_mock::{AddressStats, UtxoOrder};
 use ic_btc_interface::{
-    Address, GetCurrentFeePercentilesRequest, GetUtxosRequest, GetUtxosResponse,
-    MillisatoshiPerByte, Network, SendTransactionRequest, Utxo,
+    Address, BlockHash, GetCurrentFeePercentilesRequest, GetUtxosError, GetUtxosRequest,
+    GetUtxosResponse, Height, MillisatoshiPerByte, Network, OutPoint, Page, SendTransactionRequest,
+    Utxo, UtxosFilterInRequest,
 };
-use ic_cdk_macros::{init, update};
+use ic_cdk_macros::{init, query, update};
 use serde_bytes::ByteBuf;
 use std::cell::RefCell;
 use std::collections::{BTreeMap, BTreeSet};
 
 fn main() {}
 
+/// The default maximum number of UTXOs returned by a single
+/// `bitcoin_get_utxos` call, see `set_max_utxos_per_response`.
+const MAX_UTXOS_PER_RESPONSE: usize = 1_000;
+
 #[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
 pub struct State {
     pub fee_percentiles: Vec<u64>,
@@ -21,6 +27,29 @@ pub struct State {
     pub utxo_to_address: BTreeMap<Utxo, Address>,
     // Pending transactions.
     pub mempool: BTreeSet<ByteBuf>,
+    // Fee rates and virtual sizes of simulated mempool transactions, used to
+    // derive the fee percentiles.
+    pub mempool_fee_rates: Vec<(MillisatoshiPerByte, u32)>,
+    // The height of the current tip of the chain.
+    pub tip_height: Height,
+    // The hash of the current tip of the chain.
+    pub tip_block_hash: BlockHash,
+    // The order in which `bitcoin_get_utxos` returns UTXOs.
+    pub utxo_order: UtxoOrder,
+    // Whether all UTXOs are treated as having unlimited confirmations,
+    // regardless of their height.
+    pub instant_confirmations: bool,
+    // Outpoints spent by transactions sent with `bitcoin_send_transaction`
+    // that are not yet mined.
+    pub pending_spends: Vec<OutPoint>,
+    // UTXOs spent by mined transactions, keyed by the height of the block
+    // that includes the transaction, so that a reorg can reinstate them.
+    pub spent_utxos: BTreeMap<Height, Vec<(Address, Utxo)>>,
+    // The maximum number of UTXOs returned by a single `bitcoin_get_utxos`
+    // call before the response is paginated.
+    pub max_utxos_per_response: usize,
+    // The number of calls to each of the Bitcoin API endpoints, by method name.
+    pub call_counts: BTreeMap<String, u64>,
 }
 
 impl Default for State {
@@ -32,8 +61,207 @@ impl Default for State {
             address_to_utxos: BTreeMap::new(),
             utxo_to_address: BTreeMap::new(),
             mempool: BTreeSet::new(),
+            mempool_fee_rates: Vec::new(),
+            tip_height: 0,
+            tip_block_hash: block_hash_at_height(0),
+            utxo_order: UtxoOrder::default(),
+            instant_confirmations: false,
+            pending_spends: Vec::new(),
+            spent_utxos: BTreeMap::new(),
+            max_utxos_per_response: MAX_UTXOS_PER_RESPONSE,
+            call_counts: BTreeMap::new(),
+        }
+    }
+}
+
+/// Returns the deterministic hash of the mock block at the given height.
+fn block_hash_at_height(height: Height) -> BlockHash {
+    let mut hash = vec![0; 32];
+    hash[28..].copy_from_slice(&height.to_be_bytes());
+    hash
+}
+
+/// Encodes a page token pointing at the UTXO with the given offset in the
+/// sorted UTXO set of an address, as seen at the given tip.
+fn encode_page(tip_block_hash: &[u8], offset: usize) -> Page {
+    let mut page = tip_block_hash.to_vec();
+    page.extend_from_slice(&(offset as u64).to_be_bytes());
+    Page::from(page)
+}
+
+/// Decodes a page token produced by `encode_page`, returning the offset of the
+/// first UTXO of the page.
+fn decode_page(page: &[u8], tip_block_hash: &[u8]) -> Result<usize, GetUtxosError> {
+    if page.len() != tip_block_hash.len() + 8 {
+        return Err(GetUtxosError::MalformedPage {
+            err: format!(
+                "expected a page of {} bytes, got {} bytes",
+                tip_block_hash.len() + 8,
+                page.len()
+            ),
+        });
+    }
+    let (page_block_hash, offset) = page.split_at(tip_block_hash.len());
+    if page_block_hash != tip_block_hash {
+        return Err(GetUtxosError::UnknownTipBlockHash {
+            tip_block_hash: page_block_hash.to_vec(),
+        });
+    }
+    Ok(u64::from_be_bytes(offset.try_into().unwrap()) as usize)
+}
+
+/// Computes the 1st to 100th fee percentiles of the given transactions,
+/// weighting each fee rate by the virtual size of its transaction: the n-th
+/// percentile is the lowest fee rate such that at least n% of all bytes pay
+/// that fee rate or less.
+fn fee_percentiles(transactions: &[(MillisatoshiPerByte, u32)]) -> Vec<MillisatoshiPerByte> {
+    let mut sorted = transactions.to_vec();
+    sorted.sort_unstable();
+    let total_bytes: u64 = sorted.iter().map(|(_, vsize)| *vsize as u64).sum();
+
+    let mut percentiles = Vec::with_capacity(100);
+    let mut iter = sorted.iter();
+    let mut current = iter.next();
+    let mut cumulative_bytes = current.map(|(_, vsize)| *vsize as u64).unwrap_or_default();
+    for percentile in 1..=100u64 {
+        let target_bytes = (percentile * total_bytes + 99) / 100;
+        while cumulative_bytes < target_bytes {
+            current = iter.next();
+            cumulative_bytes += current.map(|(_, vsize)| *vsize as u64).unwrap_or_default();
+        }
+        percentiles.push(current.map(|(fee_rate, _)| *fee_rate).unwrap_or_default());
+    }
+    percentiles
+}
+
+impl State {
+    fn count_call(&mut self, method: &str) {
+        *self.call_counts.entry(method.to_string()).or_default() += 1;
+    }
+
+    fn set_tip_height(&mut self, height: Height) {
+        self.tip_height = height;
+        self.tip_block_hash = block_hash_at_height(height);
+    }
+
+    /// Returns the UTXO with the given outpoint, at any address.
+    fn utxo_with_outpoint(&self, outpoint: &OutPoint) -> Option<&Utxo> {
+        self.utxo_to_address
+            .keys()
+            .find(|utxo| &utxo.outpoint == outpoint)
+    }
+
+    /// Adds the UTXO to the address. Outpoints are unique on-chain, so an
+    /// existing UTXO with the same outpoint is replaced if `req.force` is
+    /// set, and the push is rejected otherwise.
+    fn push_utxo(
+        &mut self,
+        req: ic_bitcoin_canister_mock::PushUtxoToAddress,
+    ) -> Result<(), String> {
+        if let Some(existing) = self.utxo_with_outpoint(&req.utxo.outpoint).cloned() {
+            if !req.force.unwrap_or(false) {
+                return Err(format!(
+                    "a UTXO with outpoint {:?} already exists",
+                    req.utxo.outpoint
+                ));
+            }
+            self.remove_utxo(&existing);
+        }
+        self.insert_utxo(req.address, req.utxo);
+        Ok(())
+    }
+
+    fn insert_utxo(&mut self, address: Address, utxo: Utxo) {
+        self.utxo_to_address.insert(utxo.clone(), address.clone());
+        self.address_to_utxos
+            .entry(address)
+            .or_default()
+            .insert(utxo);
+    }
+
+    /// Removes the UTXO and returns the address it belonged to, if any.
+    fn remove_utxo(&mut self, utxo: &Utxo) -> Option<Address> {
+        let address = self.utxo_to_address.remove(utxo)?;
+        self.address_to_utxos
+            .get_mut(&address)
+            .expect("utxo not found at address")
+            .remove(utxo);
+        Some(address)
+    }
+
+    /// Removes the UTXOs spent by the pending transactions and records them as
+    /// spent in the block at `height`. Outpoints of unknown UTXOs are ignored.
+    fn apply_pending_spends(&mut self, height: Height) {
+        for outpoint in std::mem::take(&mut self.pending_spends) {
+            if let Some(utxo) = self.utxo_with_outpoint(&outpoint).cloned() {
+                let address = self.remove_utxo(&utxo).unwrap();
+                self.spent_utxos
+                    .entry(height)
+                    .or_default()
+                    .push((address, utxo));
+            }
         }
     }
+
+    /// Rolls back the blocks above `height`: the UTXOs spent in these blocks
+    /// are reinstated and the UTXOs created in them are removed.
+    fn roll_back_to(&mut self, height: Height) {
+        let rolled_back = self.spent_utxos.split_off(&height.saturating_add(1));
+        for (address, utxo) in rolled_back.into_values().flatten() {
+            self.insert_utxo(address, utxo);
+        }
+        let created: Vec<Utxo> = self
+            .utxo_to_address
+            .keys()
+            .filter(|utxo| utxo.height > height && utxo.height <= self.tip_height)
+            .cloned()
+            .collect();
+        for utxo in created {
+            self.remove_utxo(&utxo);
+        }
+    }
+}
+
+/// Returns the outpoints spent by the inputs of a serialized Bitcoin
+/// transaction, or `None` if the transaction cannot be parsed.
+fn spent_outpoints(transaction: &[u8]) -> Option<Vec<OutPoint>> {
+    fn take<'a>(bytes: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+        if bytes.len() < n {
+            return None;
+        }
+        let (head, tail) = bytes.split_at(n);
+        *bytes = tail;
+        Some(head)
+    }
+
+    fn compact_size(bytes: &mut &[u8]) -> Option<usize> {
+        let n = match take(bytes, 1)?[0] {
+            0xfd => u16::from_le_bytes(take(bytes, 2)?.try_into().ok()?) as u64,
+            0xfe => u32::from_le_bytes(take(bytes, 4)?.try_into().ok()?) as u64,
+            0xff => u64::from_le_bytes(take(bytes, 8)?.try_into().ok()?),
+            n => n as u64,
+        };
+        usize::try_from(n).ok()
+    }
+
+    let mut bytes = transaction;
+    // The version, followed by the marker and flag of the segwit encoding.
+    take(&mut bytes, 4)?;
+    if bytes.starts_with(&[0x00, 0x01]) {
+        take(&mut bytes, 2)?;
+    }
+    let num_inputs = compact_size(&mut bytes)?;
+    let mut outpoints = Vec::new();
+    for _ in 0..num_inputs {
+        let txid = take(&mut bytes, 32)?.to_vec();
+        let vout = u32::from_le_bytes(take(&mut bytes, 4)?.try_into().ok()?);
+        let script_len = compact_size(&mut bytes)?;
+        // The script signature, followed by the sequence number.
+        take(&mut bytes, script_len)?;
+        take(&mut bytes, 4)?;
+        outpoints.push(OutPoint { txid, vout });
+    }
+    Some(outpoints)
 }
 
 pub fn mutate_state<F, R>(f: F) -> R
@@ -59,63 +287,168 @@ fn init(network: Network) {
     STATE.with(|s| {
         let state = State {
             network,
-            fee_percentiles: [0; 100].into(),
-            is_available: true,
-            utxo_to_address: BTreeMap::new(),
-            address_to_utxos: BTreeMap::new(),
-            mempool: BTreeSet::new(),
+            ..Default::default()
         };
         *s.borrow_mut() = state;
     });
 }
 
+/// Returns the UTXOs of an address in the order set by `set_utxo_order`
+/// (by outpoint unless configured otherwise).
+///
+/// A `min_confirmations` filter leaves out the UTXOs with fewer confirmations,
+/// where a UTXO at the tip height has one confirmation and a UTXO above it has
+/// none. Like the Bitcoin canister, a filter exceeding the number of blocks in
+/// the chain, which is the tip height plus one for the genesis block, is
+/// rejected. If instant confirmations are enabled, the filter is ignored.
 #[candid_method(update)]
 #[update]
 fn bitcoin_get_utxos(utxos_request: GetUtxosRequest) -> GetUtxosResponse {
+    mutate_state(|s| s.count_call("bitcoin_get_utxos"));
     read_state(|s| {
         assert_eq!(utxos_request.network, s.network.into());
 
+        let (offset, min_confirmations) = match utxos_request.filter {
+            Some(UtxosFilterInRequest::Page(page)) | Some(UtxosFilterInRequest::page(page)) => {
+                let offset = decode_page(&page, &s.tip_block_hash)
+                    .unwrap_or_else(|err| ic_cdk::trap(&err.to_string()));
+                (offset, None)
+            }
+            Some(UtxosFilterInRequest::MinConfirmations(min_confirmations))
+            | Some(UtxosFilterInRequest::min_confirmations(min_confirmations)) => {
+                let max = s.tip_height.saturating_add(1);
+                if !s.instant_confirmations && min_confirmations > max {
+                    ic_cdk::trap(
+                        &GetUtxosError::MinConfirmationsTooLarge {
+                            given: min_confirmations,
+                            max,
+                        }
+                        .to_string(),
+                    );
+                }
+                (0, Some(min_confirmations))
+            }
+            None => (0, None),
+        };
+
+        let mut utxos = s
+            .address_to_utxos
+            .get(&utxos_request.address)
+            .map(|utxos| utxos.iter().cloned().collect::<Vec<Utxo>>())
+            .unwrap_or_default();
+        if let Some(min_confirmations) = min_confirmations {
+            if !s.instant_confirmations {
+                utxos.retain(|utxo| {
+                    utxo.height <= s.tip_height
+                        && s.tip_height - utxo.height + 1 >= min_confirmations
+                });
+            }
+        }
+        s.utxo_order.sort(&mut utxos);
+
+        if offset > utxos.len() {
+            ic_cdk::trap(
+                &GetUtxosError::MalformedPage {
+                    err: format!("page offset {} is out of bounds", offset),
+                }
+                .to_string(),
+            );
+        }
+
+        let end = utxos.len().min(offset + s.max_utxos_per_response);
+        let next_page = (end < utxos.len()).then(|| encode_page(&s.tip_block_hash, end));
+
         GetUtxosResponse {
-            utxos: s
-                .address_to_utxos
-                .get(&utxos_request.address)
-                .cloned()
-                .unwrap_or_default()
-                .iter()
-                .cloned()
-                .collect::<Vec<Utxo>>(),
-            tip_block_hash: vec![],
-            tip_height: 0,
-            // TODO Handle pagination.
-            next_page: None,
+            utxos: utxos[offset..end].to_vec(),
+            tip_block_hash: s.tip_block_hash.clone(),
+            tip_height: s.tip_height,
+            next_page,
         }
     })
 }
 
+/// Returns aggregate information about the UTXOs of an address as of the
+/// current tip height. Unknown addresses have no UTXOs. If instant
+/// confirmations are enabled, all UTXOs are confirmed.
+#[candid_method(update)]
+#[update]
+fn get_address_stats(address: Address) -> AddressStats {
+    read_state(|s| {
+        let mut stats = AddressStats::default();
+        for utxo in s.address_to_utxos.get(&address).into_iter().flatten() {
+            stats.utxo_count += 1;
+            stats.total_value += utxo.value;
+            if s.instant_confirmations || utxo.height <= s.tip_height {
+                stats.confirmed_value += utxo.value;
+            } else {
+                stats.pending_value += utxo.value;
+            }
+        }
+        stats
+    })
+}
+
+/// Adds a UTXO to an address.
+///
+/// Traps if a UTXO with the same outpoint already exists at any address,
+/// unless `force` is set, in which case the existing UTXO is replaced.
 #[candid_method(update)]
 #[update]
 fn push_utxo_to_address(req: ic_bitcoin_canister_mock::PushUtxoToAddress) {
+    mutate_state(|s| s.push_utxo(req)).unwrap_or_else(|err| ic_cdk::trap(&err));
+}
+
+/// Pushes all the given UTXOs, as if `push_utxo_to_address` was called for
+/// each entry in order, and returns the number of entries.
+///
+/// Every address must be a non-empty string of ASCII alphanumeric characters,
+/// which holds for both base58 and bech32 encoded addresses. The network of
+/// an address is not checked. Unless `force` is set, the outpoint of an entry
+/// must not be used by an existing UTXO or by an earlier entry. If some entry
+/// is invalid, an error identifying the first such entry is returned and no
+/// UTXO is pushed.
+#[candid_method(update)]
+#[update]
+fn push_utxos_batch(
+    entries: Vec<ic_bitcoin_canister_mock::PushUtxoToAddress>,
+) -> Result<u64, String> {
+    read_state(|s| {
+        let mut outpoints = BTreeSet::new();
+        for (index, entry) in entries.iter().enumerate() {
+            if entry.address.is_empty() || !entry.address.chars().all(|c| c.is_ascii_alphanumeric())
+            {
+                return Err(format!(
+                    "entry {}: invalid address {:?}",
+                    index, entry.address
+                ));
+            }
+            let outpoint = &entry.utxo.outpoint;
+            let is_duplicate =
+                outpoints.contains(outpoint) || s.utxo_with_outpoint(outpoint).is_some();
+            if is_duplicate && !entry.force.unwrap_or(false) {
+                return Err(format!(
+                    "entry {}: a UTXO with outpoint {:?} already exists",
+                    index, outpoint
+                ));
+            }
+            outpoints.insert(outpoint.clone());
+        }
+        Ok(())
+    })?;
+    let count = entries.len() as u64;
     mutate_state(|s| {
-        s.utxo_to_address
-            .insert(req.utxo.clone(), req.address.clone());
-        s.address_to_utxos
-            .entry(req.address)
-            .or_default()
-            .insert(req.utxo);
+        for entry in entries {
+            s.push_utxo(entry)
+                .expect("BUG: entries were checked for duplicate outpoints");
+        }
     });
+    Ok(count)
 }
 
 #[candid_method(update)]
 #[update]
 fn remove_utxo(utxo: Utxo) {
-    let address = read_state(|s| s.utxo_to_address.get(&utxo).cloned().unwrap());
-    mutate_state(|s| {
-        s.utxo_to_address.remove(&utxo);
-        s.address_to_utxos
-            .get_mut(&address)
-            .expect("utxo not found at address")
-            .remove(&utxo);
-    });
+    mutate_state(|s| s.remove_utxo(&utxo)).unwrap();
 }
 
 #[candid_method(update)]
@@ -123,7 +456,14 @@ fn remove_utxo(utxo: Utxo) {
 fn bitcoin_get_current_fee_percentiles(
     _: GetCurrentFeePercentilesRequest,
 ) -> Vec<MillisatoshiPerByte> {
-    read_state(|s| s.fee_percentiles.clone())
+    mutate_state(|s| s.count_call("bitcoin_get_current_fee_percentiles"));
+    read_state(|s| {
+        if s.mempool_fee_rates.is_empty() {
+            s.fee_percentiles.clone()
+        } else {
+            fee_percentiles(&s.mempool_fee_rates)
+        }
+    })
 }
 
 #[candid_method(update)]
@@ -132,12 +472,23 @@ fn set_fee_percentiles(fee_percentiles: Vec<MillisatoshiPerByte>) {
     mutate_state(|s| s.fee_percentiles = fee_percentiles);
 }
 
+/// Adds the transaction to the mempool if the canister is available.
+///
+/// Like with the Bitcoin canister, the UTXOs spent by the transaction's inputs
+/// are still returned until the next block is mined with `mine_blocks`, which
+/// removes them and records them as spent in that block, so that `reorg` past
+/// that block reinstates them. Transactions that cannot be parsed spend
+/// nothing.
 #[candid_method(update)]
 #[update]
 fn bitcoin_send_transaction(transaction: SendTransactionRequest) {
     mutate_state(|s| {
+        s.count_call("bitcoin_send_transaction");
         assert_eq!(transaction.network, s.network.into());
         if s.is_available {
+            if let Some(outpoints) = spent_outpoints(&transaction.transaction) {
+                s.pending_spends.extend(outpoints);
+            }
             s.mempool.insert(ByteBuf::from(transaction.transaction));
         }
     })
@@ -155,10 +506,138 @@ fn get_mempool() -> Vec<ByteBuf> {
     read_state(|s| s.mempool.iter().cloned().collect::<Vec<ByteBuf>>())
 }
 
+/// Adds a simulated transaction to the mempool. While there are any, the fee
+/// percentiles are derived from them instead of those set with
+/// `set_fee_percentiles`.
+#[candid_method(update)]
+#[update]
+fn add_mempool_transaction(fee_rate: MillisatoshiPerByte, vsize: u32) {
+    assert!(vsize > 0, "transaction virtual size must be positive");
+    mutate_state(|s| s.mempool_fee_rates.push((fee_rate, vsize)));
+}
+
+/// Removes all transactions from the mempool, including the sent ones, whose
+/// inputs then stay unspent.
 #[candid_method(update)]
 #[update]
 fn reset_mempool() {
-    mutate_state(|s| s.mempool = BTreeSet::new());
+    mutate_state(|s| {
+        s.mempool = BTreeSet::new();
+        s.mempool_fee_rates = Vec::new();
+        s.pending_spends = Vec::new();
+    });
+}
+
+/// Sets the order in which `bitcoin_get_utxos` returns UTXOs, which defaults
+/// to `OutPoint`, i.e. by transaction ID, then by output index.
+///
+/// The other orders sort by height (`HeightAscending`, `HeightDescending`) or
+/// by value (`ValueAscending`, `ValueDescending`), breaking ties by outpoint.
+/// Pagination follows the same order.
+#[candid_method(update)]
+#[update]
+fn set_utxo_order(order: UtxoOrder) {
+    mutate_state(|s| s.utxo_order = order);
+}
+
+/// Sets the maximum number of UTXOs returned by a single `bitcoin_get_utxos`
+/// call, which defaults to the limit of the Bitcoin canister. Responses with
+/// more UTXOs are paginated.
+///
+/// Traps if `max` is zero.
+#[candid_method(update)]
+#[update]
+fn set_max_utxos_per_response(max: usize) {
+    if max == 0 {
+        ic_cdk::trap("the maximum number of UTXOs per response must be positive");
+    }
+    mutate_state(|s| s.max_utxos_per_response = max);
+}
+
+/// Enables or disables instant confirmations, which are disabled by default.
+///
+/// While enabled, all UTXOs are treated as having unlimited confirmations,
+/// regardless of their height and the tip height, so tests that do not care
+/// about confirmations need not mine blocks.
+#[candid_method(update)]
+#[update]
+fn set_instant_confirmations(enabled: bool) {
+    mutate_state(|s| s.instant_confirmations = enabled);
+}
+
+/// Advances the tip height by `count` blocks, so that UTXOs gain `count`
+/// confirmations. The first of the new blocks includes the transactions sent
+/// since the last block was mined, whose inputs are spent from then on. The
+/// tip block hash changes with the height, so page tokens returned before the
+/// call are rejected afterwards.
+///
+/// Traps if the new tip height would overflow.
+#[candid_method(update)]
+#[update]
+fn mine_blocks(count: u32) {
+    mutate_state(|s| {
+        let height = s
+            .tip_height
+            .checked_add(count)
+            .expect("tip height overflow");
+        if count > 0 {
+            s.apply_pending_spends(s.tip_height + 1);
+        }
+        s.set_tip_height(height);
+    });
+}
+
+/// Rolls back the `depth` most recent blocks, returning the UTXO set to what
+/// it was at the new tip height: UTXOs spent in the rolled-back blocks are
+/// reinstated and UTXOs created in them are removed. UTXOs at heights above
+/// the current tip are left alone, and transactions that were sent but not yet
+/// mined stay pending.
+#[candid_method(update)]
+#[update]
+fn reorg(depth: u32) {
+    mutate_state(|s| {
+        let height = s
+            .tip_height
+            .checked_sub(depth)
+            .expect("reorg depth exceeds the tip height");
+        s.roll_back_to(height);
+        s.set_tip_height(height);
+    });
+}
+
+/// Returns the number of calls to each Bitcoin API endpoint of the mock since
+/// it was installed or the counts were last reset with `reset_call_counts`, by
+/// method name. Methods that were not called are omitted, as are calls that
+/// trapped.
+///
+/// Only the endpoints that the mock implements are counted, i.e.
+/// `bitcoin_get_utxos`, `bitcoin_get_current_fee_percentiles` and
+/// `bitcoin_send_transaction`. The mock has no `bitcoin_get_balance` or
+/// `bitcoin_get_block_headers` endpoints.
+#[candid_method(query)]
+#[query]
+fn get_call_counts() -> BTreeMap<String, u64> {
+    read_state(|s| s.call_counts.clone())
+}
+
+/// Resets the counts returned by `get_call_counts` to zero. The mock has no
+/// endpoint resetting its whole state, so the counts are reset separately from
+/// the mempool and the UTXOs.
+#[candid_method(update)]
+#[update]
+fn reset_call_counts() {
+    mutate_state(|s| s.call_counts = BTreeMap::new());
+}
+
+/// Overrides the hash of the tip block until the tip height next changes.
+///
+/// Page tokens of paginated `bitcoin_get_utxos` responses embed the tip block
+/// hash, so tokens returned before the call are rejected with
+/// `UnknownTipBlockHash` afterwards, as they would be after a new block.
+#[candid_method(update)]
+#[update]
+fn set_tip_block_hash(hash: [u8; 32]) {
+    mutate_state(|s| s.tip_block_hash = hash.to_vec());
 }
 
 #[test]
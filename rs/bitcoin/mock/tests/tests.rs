@@ -1,16 +1,20 @@
-use bitcoin::consensus::deserialize;
-use bitcoin::Transaction;
+use bitcoin::consensus::{deserialize, serialize};
+use bitcoin::hashes::Hash;
+use bitcoin::{Script, Transaction, TxIn, Txid, Witness};
 use candid::{Decode, Encode};
 use hex::FromHex;
-use ic_bitcoin_canister_mock::PushUtxoToAddress;
+use ic_bitcoin_canister_mock::{AddressStats, PushUtxoToAddress, UtxoOrder};
 use ic_btc_interface::{
     GetCurrentFeePercentilesRequest, GetUtxosRequest, GetUtxosResponse, MillisatoshiPerByte,
-    Network, NetworkInRequest, OutPoint, SendTransactionRequest, Utxo,
+    Network, NetworkInRequest, OutPoint, SendTransactionRequest, Utxo, UtxosFilterInRequest,
+};
+use ic_state_machine_tests::{
+    CanisterId, Cycles, PrincipalId, StateMachine, StateMachineBuilder, UserError,
 };
-use ic_state_machine_tests::{CanisterId, Cycles, PrincipalId, StateMachine, StateMachineBuilder};
 use ic_test_utilities_load_wasm::load_wasm;
 use ic_universal_canister::{call_args, wasm, UNIVERSAL_CANISTER_WASM};
 use rand::{thread_rng, Rng};
+use std::collections::{BTreeMap, BTreeSet};
 use std::str::FromStr;
 
 fn generate_tx_id() -> Vec<u8> {
@@ -88,6 +92,7 @@ fn test_install_bitcoin_mock_canister() {
                 },
                 value,
             },
+            force: None,
         })
         .unwrap(),
     );
@@ -186,3 +191,839 @@ fn test_install_bitcoin_mock_canister() {
 
     assert_eq!(fee_percentiles, decoded_percentiles);
 }
+
+fn get_utxos_with_filter(
+    env: &StateMachine,
+    address: &str,
+    filter: Option<UtxosFilterInRequest>,
+) -> Result<GetUtxosResponse, UserError> {
+    env.execute_ingress(
+        testnet_bitcoin_canister_id(),
+        "bitcoin_get_utxos",
+        Encode!(&GetUtxosRequest {
+            address: address.to_string(),
+            filter,
+            network: NetworkInRequest::Regtest
+        })
+        .unwrap(),
+    )
+    .map(|result| {
+        Decode!(&result.bytes(), GetUtxosResponse)
+            .expect("failed to decode bitcoin_get_utxos response")
+    })
+}
+
+fn get_utxos(env: &StateMachine, address: &str) -> GetUtxosResponse {
+    get_utxos_with_filter(env, address, None).expect("failed to call bitcoin_get_utxos")
+}
+
+fn try_push_utxo(
+    env: &StateMachine,
+    address: &str,
+    utxo: Utxo,
+    force: bool,
+) -> Result<(), UserError> {
+    env.execute_ingress(
+        testnet_bitcoin_canister_id(),
+        "push_utxo_to_address",
+        Encode!(&PushUtxoToAddress {
+            address: address.to_string(),
+            utxo,
+            force: Some(force),
+        })
+        .unwrap(),
+    )
+    .map(|_| ())
+}
+
+fn push_utxo(env: &StateMachine, address: &str, utxo: Utxo) {
+    try_push_utxo(env, address, utxo, false).expect("failed to push a UTXO");
+}
+
+fn random_utxo(height: u32, value: u64) -> Utxo {
+    Utxo {
+        height,
+        outpoint: OutPoint {
+            txid: generate_tx_id(),
+            vout: 0,
+        },
+        value,
+    }
+}
+
+fn mine_blocks(env: &StateMachine, count: u32) {
+    env.execute_ingress(
+        testnet_bitcoin_canister_id(),
+        "mine_blocks",
+        Encode!(&count).unwrap(),
+    )
+    .expect("failed to mine blocks");
+}
+
+fn setup_bitcoin_mock() -> StateMachine {
+    let mock_id = testnet_bitcoin_canister_id();
+    let env = StateMachineBuilder::new()
+        .with_default_canister_range()
+        .with_extra_canister_range(mock_id..=mock_id)
+        .build();
+    install_bitcoin_mock_canister(&env);
+    env
+}
+
+#[test]
+fn test_tip_block_hash_changes_with_height() {
+    let env = setup_bitcoin_mock();
+    let address = "31xxvrZWyZohLR5CKE3wTqur6rbEfi5HUz";
+
+    let first = get_utxos(&env, address);
+    let second = get_utxos(&env, address);
+    assert_eq!(first.tip_height, 0);
+    assert_eq!(first.tip_block_hash, second.tip_block_hash);
+    assert_eq!(first.tip_block_hash.len(), 32);
+
+    mine_blocks(&env, 1);
+    let mined = get_utxos(&env, address);
+    assert_eq!(mined.tip_height, 1);
+    assert_ne!(mined.tip_block_hash, first.tip_block_hash);
+    assert_eq!(
+        mined.tip_block_hash,
+        get_utxos(&env, address).tip_block_hash
+    );
+
+    let custom_hash = [7u8; 32];
+    env.execute_ingress(
+        testnet_bitcoin_canister_id(),
+        "set_tip_block_hash",
+        Encode!(&custom_hash).unwrap(),
+    )
+    .expect("failed to set the tip block hash");
+    let custom = get_utxos(&env, address);
+    assert_eq!(custom.tip_height, 1);
+    assert_eq!(custom.tip_block_hash, custom_hash.to_vec());
+
+    mine_blocks(&env, 2);
+    let mined = get_utxos(&env, address);
+    assert_eq!(mined.tip_height, 3);
+    assert_ne!(mined.tip_block_hash, custom_hash.to_vec());
+
+    env.execute_ingress(
+        testnet_bitcoin_canister_id(),
+        "reorg",
+        Encode!(&2_u32).unwrap(),
+    )
+    .expect("failed to reorg");
+    let reorged = get_utxos(&env, address);
+    assert_eq!(reorged.tip_height, 1);
+    assert_ne!(reorged.tip_block_hash, mined.tip_block_hash);
+}
+
+#[test]
+fn test_get_utxos_pagination() {
+    let env = setup_bitcoin_mock();
+    let address = "31xxvrZWyZohLR5CKE3wTqur6rbEfi5HUz";
+
+    let num_utxos = 2_100;
+    let mut expected = BTreeSet::new();
+    for i in 0..num_utxos {
+        let utxo = random_utxo(0, i + 1);
+        expected.insert(utxo.clone());
+        push_utxo(&env, address, utxo);
+    }
+
+    let mut response = get_utxos(&env, address);
+    let mut pages = 1;
+    let mut received = response.utxos.clone();
+    while let Some(page) = response.next_page {
+        response = get_utxos_with_filter(&env, address, Some(UtxosFilterInRequest::Page(page)))
+            .expect("failed to fetch the next page");
+        received.extend(response.utxos.iter().cloned());
+        pages += 1;
+    }
+    assert_eq!(pages, 3);
+    assert_eq!(received.len(), num_utxos as usize);
+    assert_eq!(received.into_iter().collect::<BTreeSet<_>>(), expected);
+
+    let err = get_utxos_with_filter(
+        &env,
+        address,
+        Some(UtxosFilterInRequest::page(vec![1, 2, 3].into())),
+    )
+    .unwrap_err();
+    assert!(
+        err.description().contains("The provided page is malformed"),
+        "unexpected error: {}",
+        err
+    );
+
+    // Pages are bound to the tip they were issued at.
+    let page = get_utxos(&env, address).next_page.unwrap();
+    mine_blocks(&env, 1);
+    let err =
+        get_utxos_with_filter(&env, address, Some(UtxosFilterInRequest::Page(page))).unwrap_err();
+    assert!(
+        err.description().contains("is unknown"),
+        "unexpected error: {}",
+        err
+    );
+}
+
+fn try_set_max_utxos_per_response(env: &StateMachine, max: usize) -> Result<(), UserError> {
+    env.execute_ingress(
+        testnet_bitcoin_canister_id(),
+        "set_max_utxos_per_response",
+        Encode!(&max).unwrap(),
+    )
+    .map(|_| ())
+}
+
+fn set_max_utxos_per_response(env: &StateMachine, max: usize) {
+    try_set_max_utxos_per_response(env, max)
+        .expect("failed to set the maximum number of UTXOs per response");
+}
+
+#[test]
+fn test_get_utxos_configurable_page_size() {
+    let env = setup_bitcoin_mock();
+    let address = "31xxvrZWyZohLR5CKE3wTqur6rbEfi5HUz";
+
+    let num_utxos = 25;
+    let mut expected = BTreeSet::new();
+    for i in 0..num_utxos {
+        let utxo = random_utxo(0, i + 1);
+        expected.insert(utxo.clone());
+        push_utxo(&env, address, utxo);
+    }
+    let response = get_utxos(&env, address);
+    assert_eq!(response.utxos.len(), num_utxos as usize);
+    assert_eq!(response.next_page, None);
+
+    set_max_utxos_per_response(&env, 10);
+    let mut response = get_utxos(&env, address);
+    let mut page_sizes = vec![response.utxos.len()];
+    let mut received = response.utxos.clone();
+    while let Some(page) = response.next_page {
+        response = get_utxos_with_filter(&env, address, Some(UtxosFilterInRequest::Page(page)))
+            .expect("failed to fetch the next page");
+        page_sizes.push(response.utxos.len());
+        received.extend(response.utxos.iter().cloned());
+    }
+    assert_eq!(page_sizes, vec![10, 10, 5]);
+    assert_eq!(received.into_iter().collect::<BTreeSet<_>>(), expected);
+
+    let err = try_set_max_utxos_per_response(&env, 0).unwrap_err();
+    assert!(
+        err.description().contains("must be positive"),
+        "unexpected error: {}",
+        err
+    );
+}
+
+#[test]
+fn test_get_utxos_min_confirmations_filter() {
+    let env = setup_bitcoin_mock();
+    let address = "31xxvrZWyZohLR5CKE3wTqur6rbEfi5HUz";
+    push_utxo(&env, address, random_utxo(0, 1_000));
+
+    // At height 0 the chain consists of the genesis block only.
+    for filter in [
+        UtxosFilterInRequest::MinConfirmations(0),
+        UtxosFilterInRequest::min_confirmations(1),
+    ] {
+        let response = get_utxos_with_filter(&env, address, Some(filter)).unwrap();
+        assert_eq!(response.utxos.len(), 1);
+    }
+
+    let err = get_utxos_with_filter(
+        &env,
+        address,
+        Some(UtxosFilterInRequest::MinConfirmations(2)),
+    )
+    .unwrap_err();
+    assert!(
+        err.description()
+            .contains("The requested min_confirmations is too large. Given: 2, max supported: 1"),
+        "unexpected error: {}",
+        err
+    );
+
+    mine_blocks(&env, 5);
+    assert!(get_utxos_with_filter(
+        &env,
+        address,
+        Some(UtxosFilterInRequest::min_confirmations(6))
+    )
+    .is_ok());
+    let err = get_utxos_with_filter(
+        &env,
+        address,
+        Some(UtxosFilterInRequest::min_confirmations(7)),
+    )
+    .unwrap_err();
+    assert!(
+        err.description()
+            .contains("The requested min_confirmations is too large. Given: 7, max supported: 6"),
+        "unexpected error: {}",
+        err
+    );
+}
+
+fn set_instant_confirmations(env: &StateMachine, enabled: bool) {
+    env.execute_ingress(
+        testnet_bitcoin_canister_id(),
+        "set_instant_confirmations",
+        Encode!(&enabled).unwrap(),
+    )
+    .expect("failed to set instant confirmations");
+}
+
+#[test]
+fn test_instant_confirmations() {
+    let env = setup_bitcoin_mock();
+    let address = "31xxvrZWyZohLR5CKE3wTqur6rbEfi5HUz";
+    let old = random_utxo(0, 1_000);
+    push_utxo(&env, address, old.clone());
+    mine_blocks(&env, 5);
+    let fresh = random_utxo(5, 2_000);
+    push_utxo(&env, address, fresh.clone());
+    let pending = random_utxo(10, 4_000);
+    push_utxo(&env, address, pending.clone());
+    let utxos_with_confirmations = |min_confirmations| {
+        let mut utxos = get_utxos_with_filter(
+            &env,
+            address,
+            Some(UtxosFilterInRequest::MinConfirmations(min_confirmations)),
+        )
+        .map(|response| response.utxos);
+        if let Ok(utxos) = &mut utxos {
+            utxos.sort_by_key(|utxo| utxo.value);
+        }
+        utxos
+    };
+
+    // Disabled by default: only the UTXO at height 0 has 6 confirmations, the
+    // chain is too short for 7 confirmations and the UTXO above the tip height
+    // is pending.
+    assert_eq!(utxos_with_confirmations(6).unwrap(), vec![old.clone()]);
+    assert!(utxos_with_confirmations(7).is_err());
+    assert_eq!(get_address_stats(&env, address).confirmed_value, 3_000);
+
+    set_instant_confirmations(&env, true);
+    let all = vec![old.clone(), fresh, pending];
+    assert_eq!(utxos_with_confirmations(6).unwrap(), all);
+    assert_eq!(utxos_with_confirmations(7).unwrap(), all);
+    assert_eq!(
+        get_address_stats(&env, address),
+        AddressStats {
+            utxo_count: 3,
+            total_value: 7_000,
+            confirmed_value: 7_000,
+            pending_value: 0,
+        }
+    );
+
+    set_instant_confirmations(&env, false);
+    assert_eq!(utxos_with_confirmations(6).unwrap(), vec![old]);
+    assert!(utxos_with_confirmations(7).is_err());
+    assert_eq!(get_address_stats(&env, address).pending_value, 4_000);
+}
+
+fn set_utxo_order(env: &StateMachine, order: UtxoOrder) {
+    env.execute_ingress(
+        testnet_bitcoin_canister_id(),
+        "set_utxo_order",
+        Encode!(&order).unwrap(),
+    )
+    .expect("failed to set the UTXO order");
+}
+
+#[test]
+fn test_get_utxos_order() {
+    let env = setup_bitcoin_mock();
+    let address = "31xxvrZWyZohLR5CKE3wTqur6rbEfi5HUz";
+
+    let utxo = |txid: u8, vout: u32, height: u32, value: u64| Utxo {
+        height,
+        outpoint: OutPoint {
+            txid: vec![txid; 32],
+            vout,
+        },
+        value,
+    };
+    let a = utxo(1, 0, 5, 300);
+    let b = utxo(1, 1, 7, 100);
+    let c = utxo(2, 0, 5, 200);
+    let d = utxo(3, 0, 6, 100);
+
+    // Insert out of order; the default order is by txid, then vout.
+    for u in [&d, &b, &c, &a] {
+        push_utxo(&env, address, u.clone());
+    }
+    let by_outpoint = vec![a.clone(), b.clone(), c.clone(), d.clone()];
+    assert_eq!(get_utxos(&env, address).utxos, by_outpoint);
+    assert_eq!(get_utxos(&env, address).utxos, by_outpoint);
+
+    // Ties are broken by outpoint.
+    set_utxo_order(&env, UtxoOrder::HeightAscending);
+    assert_eq!(
+        get_utxos(&env, address).utxos,
+        vec![a.clone(), c.clone(), d.clone(), b.clone()]
+    );
+
+    set_utxo_order(&env, UtxoOrder::HeightDescending);
+    assert_eq!(
+        get_utxos(&env, address).utxos,
+        vec![b.clone(), d.clone(), a.clone(), c.clone()]
+    );
+
+    set_utxo_order(&env, UtxoOrder::ValueAscending);
+    assert_eq!(
+        get_utxos(&env, address).utxos,
+        vec![b.clone(), d.clone(), c.clone(), a.clone()]
+    );
+
+    set_utxo_order(&env, UtxoOrder::ValueDescending);
+    assert_eq!(
+        get_utxos(&env, address).utxos,
+        vec![a.clone(), c.clone(), b.clone(), d.clone()]
+    );
+
+    set_utxo_order(&env, UtxoOrder::OutPoint);
+    assert_eq!(get_utxos(&env, address).utxos, by_outpoint);
+}
+
+fn get_fee_percentiles(env: &StateMachine) -> Vec<MillisatoshiPerByte> {
+    Decode!(
+        &env.execute_ingress(
+            testnet_bitcoin_canister_id(),
+            "bitcoin_get_current_fee_percentiles",
+            Encode!(&GetCurrentFeePercentilesRequest {
+                network: NetworkInRequest::Regtest
+            })
+            .unwrap(),
+        )
+        .expect("failed to get the fee percentiles")
+        .bytes(),
+        Vec<MillisatoshiPerByte>
+    )
+    .expect("failed to decode bitcoin_get_current_fee_percentiles response")
+}
+
+fn add_mempool_transaction(env: &StateMachine, fee_rate: MillisatoshiPerByte, vsize: u32) {
+    env.execute_ingress(
+        testnet_bitcoin_canister_id(),
+        "add_mempool_transaction",
+        Encode!(&fee_rate, &vsize).unwrap(),
+    )
+    .expect("failed to add a mempool transaction");
+}
+
+#[test]
+fn test_fee_percentiles_from_mempool() {
+    let env = setup_bitcoin_mock();
+
+    let static_percentiles: Vec<MillisatoshiPerByte> = (1..=100).collect();
+    env.execute_ingress(
+        testnet_bitcoin_canister_id(),
+        "set_fee_percentiles",
+        Encode!(&static_percentiles).unwrap(),
+    )
+    .expect("failed to set the fee percentiles");
+    assert_eq!(get_fee_percentiles(&env), static_percentiles);
+
+    // A single transaction determines all percentiles.
+    add_mempool_transaction(&env, 2_000, 250);
+    assert_eq!(get_fee_percentiles(&env), vec![2_000; 100]);
+
+    // 100 bytes at 1000 and 300 bytes at 3000 msat/byte: the cheaper
+    // transaction covers the lowest 25% of the bytes.
+    env.execute_ingress(
+        testnet_bitcoin_canister_id(),
+        "reset_mempool",
+        Encode!().unwrap(),
+    )
+    .expect("failed to reset the mempool");
+    assert_eq!(get_fee_percentiles(&env), static_percentiles);
+
+    add_mempool_transaction(&env, 3_000, 300);
+    add_mempool_transaction(&env, 1_000, 100);
+    let mut expected = vec![1_000; 25];
+    expected.extend(vec![3_000; 75]);
+    assert_eq!(get_fee_percentiles(&env), expected);
+
+    // Equal sizes at rates 1..=4 split the percentiles evenly.
+    env.execute_ingress(
+        testnet_bitcoin_canister_id(),
+        "reset_mempool",
+        Encode!().unwrap(),
+    )
+    .expect("failed to reset the mempool");
+    for fee_rate in [4, 2, 3, 1] {
+        add_mempool_transaction(&env, fee_rate, 10);
+    }
+    let expected: Vec<MillisatoshiPerByte> = (1..=4).flat_map(|r| vec![r; 25]).collect();
+    assert_eq!(get_fee_percentiles(&env), expected);
+}
+
+fn get_address_stats(env: &StateMachine, address: &str) -> AddressStats {
+    Decode!(
+        &env.execute_ingress(
+            testnet_bitcoin_canister_id(),
+            "get_address_stats",
+            Encode!(&address.to_string()).unwrap(),
+        )
+        .expect("failed to get the address stats")
+        .bytes(),
+        AddressStats
+    )
+    .expect("failed to decode get_address_stats response")
+}
+
+#[test]
+fn test_get_address_stats() {
+    let env = setup_bitcoin_mock();
+    let address = "31xxvrZWyZohLR5CKE3wTqur6rbEfi5HUz";
+    let other_address = "36d8AewQvoKjHPbaeFFkqJHpoZ8wnrTMeU";
+
+    assert_eq!(get_address_stats(&env, address), AddressStats::default());
+
+    push_utxo(&env, address, random_utxo(0, 1_000));
+    push_utxo(&env, address, random_utxo(0, 2_000));
+    // UTXOs above the tip height are not confirmed yet.
+    push_utxo(&env, address, random_utxo(1, 30_000));
+    push_utxo(&env, address, random_utxo(3, 400_000));
+    push_utxo(&env, other_address, random_utxo(0, 5));
+
+    assert_eq!(
+        get_address_stats(&env, address),
+        AddressStats {
+            utxo_count: 4,
+            total_value: 433_000,
+            confirmed_value: 3_000,
+            pending_value: 430_000,
+        }
+    );
+
+    mine_blocks(&env, 1);
+    assert_eq!(
+        get_address_stats(&env, address),
+        AddressStats {
+            utxo_count: 4,
+            total_value: 433_000,
+            confirmed_value: 33_000,
+            pending_value: 400_000,
+        }
+    );
+
+    mine_blocks(&env, 2);
+    assert_eq!(
+        get_address_stats(&env, address),
+        AddressStats {
+            utxo_count: 4,
+            total_value: 433_000,
+            confirmed_value: 433_000,
+            pending_value: 0,
+        }
+    );
+
+    assert_eq!(get_address_stats(&env, "unknown"), AddressStats::default());
+}
+
+fn push_utxos_batch(env: &StateMachine, entries: Vec<PushUtxoToAddress>) -> Result<u64, String> {
+    Decode!(
+        &env.execute_ingress(
+            testnet_bitcoin_canister_id(),
+            "push_utxos_batch",
+            Encode!(&entries).unwrap(),
+        )
+        .expect("failed to push a batch of UTXOs")
+        .bytes(),
+        Result<u64, String>
+    )
+    .expect("failed to decode push_utxos_batch response")
+}
+
+#[test]
+fn test_push_utxos_batch() {
+    let env = setup_bitcoin_mock();
+    let addresses = [
+        "31xxvrZWyZohLR5CKE3wTqur6rbEfi5HUz",
+        "36d8AewQvoKjHPbaeFFkqJHpoZ8wnrTMeU",
+        "bcrt1qa8zddhqjtwr9jy6f8x4gqkn8rvzwtdpe0umyq8",
+    ];
+
+    let mut expected: Vec<BTreeSet<Utxo>> = vec![BTreeSet::new(); addresses.len()];
+    let mut entries = vec![];
+    for i in 0..10 {
+        let utxo = random_utxo(0, 1_000 + i as u64);
+        let address_index = i % addresses.len();
+        expected[address_index].insert(utxo.clone());
+        entries.push(PushUtxoToAddress {
+            address: addresses[address_index].to_string(),
+            utxo,
+            force: None,
+        });
+    }
+
+    assert_eq!(push_utxos_batch(&env, entries), Ok(10));
+    for (address, expected_utxos) in addresses.iter().zip(expected.iter()) {
+        let utxos: BTreeSet<Utxo> = get_utxos(&env, address).utxos.into_iter().collect();
+        assert_eq!(&utxos, expected_utxos);
+    }
+
+    assert_eq!(push_utxos_batch(&env, vec![]), Ok(0));
+
+    // An invalid entry rejects the whole batch.
+    let bad_batch = vec![
+        PushUtxoToAddress {
+            address: addresses[0].to_string(),
+            utxo: random_utxo(0, 1),
+            force: None,
+        },
+        PushUtxoToAddress {
+            address: "not an address".to_string(),
+            utxo: random_utxo(0, 2),
+            force: None,
+        },
+        PushUtxoToAddress {
+            address: String::new(),
+            utxo: random_utxo(0, 3),
+            force: None,
+        },
+    ];
+    let err = push_utxos_batch(&env, bad_batch).unwrap_err();
+    assert!(err.starts_with("entry 1:"), "{}", err);
+    assert_eq!(get_utxos(&env, addresses[0]).utxos.len(), expected[0].len());
+}
+
+#[test]
+fn test_push_duplicate_outpoint() {
+    let env = setup_bitcoin_mock();
+    let address0 = "31xxvrZWyZohLR5CKE3wTqur6rbEfi5HUz";
+    let address1 = "36d8AewQvoKjHPbaeFFkqJHpoZ8wnrTMeU";
+
+    let utxo = random_utxo(0, 1_000);
+    push_utxo(&env, address0, utxo.clone());
+
+    // The same outpoint is rejected at any address, even with another value.
+    let duplicate = Utxo {
+        value: 2_000,
+        ..utxo.clone()
+    };
+    for address in [address0, address1] {
+        let err = try_push_utxo(&env, address, duplicate.clone(), false).unwrap_err();
+        assert!(
+            err.description().contains("already exists"),
+            "unexpected error: {}",
+            err
+        );
+    }
+    assert_eq!(get_utxos(&env, address0).utxos, vec![utxo.clone()]);
+    assert!(get_utxos(&env, address1).utxos.is_empty());
+    assert_eq!(get_address_stats(&env, address0).total_value, 1_000);
+
+    // Forcing the push moves the outpoint to the new UTXO and address.
+    try_push_utxo(&env, address1, duplicate.clone(), true).unwrap();
+    assert!(get_utxos(&env, address0).utxos.is_empty());
+    assert_eq!(get_utxos(&env, address1).utxos, vec![duplicate.clone()]);
+
+    // Batches are checked against existing UTXOs and against earlier entries.
+    let fresh = random_utxo(0, 3_000);
+    let entry = |utxo: &Utxo, force| PushUtxoToAddress {
+        address: address0.to_string(),
+        utxo: utxo.clone(),
+        force,
+    };
+    let err =
+        push_utxos_batch(&env, vec![entry(&fresh, None), entry(&duplicate, None)]).unwrap_err();
+    assert!(err.starts_with("entry 1:"), "{}", err);
+    let err =
+        push_utxos_batch(&env, vec![entry(&fresh, None), entry(&fresh, Some(false))]).unwrap_err();
+    assert!(err.starts_with("entry 1:"), "{}", err);
+    assert!(get_utxos(&env, address0).utxos.is_empty());
+
+    assert_eq!(
+        push_utxos_batch(
+            &env,
+            vec![entry(&fresh, None), entry(&duplicate, Some(true))]
+        ),
+        Ok(2)
+    );
+    let utxos: BTreeSet<Utxo> = get_utxos(&env, address0).utxos.into_iter().collect();
+    assert_eq!(utxos, BTreeSet::from([fresh, duplicate]));
+    assert!(get_utxos(&env, address1).utxos.is_empty());
+}
+
+fn send_transaction(env: &StateMachine, transaction: Vec<u8>) {
+    env.execute_ingress(
+        testnet_bitcoin_canister_id(),
+        "bitcoin_send_transaction",
+        Encode!(&SendTransactionRequest {
+            transaction,
+            network: NetworkInRequest::Regtest,
+        })
+        .unwrap(),
+    )
+    .expect("failed to send a transaction");
+}
+
+/// Returns a serialized segwit transaction spending the given UTXOs.
+fn spending_transaction(utxos: &[&Utxo]) -> Vec<u8> {
+    let transaction = Transaction {
+        version: 2,
+        lock_time: 0,
+        input: utxos
+            .iter()
+            .map(|utxo| TxIn {
+                previous_output: bitcoin::OutPoint {
+                    txid: Txid::from_slice(&utxo.outpoint.txid).unwrap(),
+                    vout: utxo.outpoint.vout,
+                },
+                script_sig: Script::new(),
+                sequence: 0xffffffff,
+                witness: Witness::from_vec(vec![vec![1; 72], vec![2; 33]]),
+            })
+            .collect(),
+        output: vec![],
+    };
+    serialize(&transaction)
+}
+
+fn reorg(env: &StateMachine, depth: u32) {
+    env.execute_ingress(
+        testnet_bitcoin_canister_id(),
+        "reorg",
+        Encode!(&depth).unwrap(),
+    )
+    .expect("failed to reorg");
+}
+
+#[test]
+fn test_reorg_restores_spent_utxos() {
+    let env = setup_bitcoin_mock();
+    let address0 = "31xxvrZWyZohLR5CKE3wTqur6rbEfi5HUz";
+    let address1 = "36d8AewQvoKjHPbaeFFkqJHpoZ8wnrTMeU";
+
+    let spent = random_utxo(1, 1_000);
+    let kept = random_utxo(1, 2_000);
+    push_utxo(&env, address0, spent.clone());
+    push_utxo(&env, address0, kept.clone());
+    mine_blocks(&env, 2);
+    let before = get_utxos(&env, address0);
+    let stats_before = get_address_stats(&env, address0);
+    assert_eq!(stats_before.confirmed_value, 3_000);
+
+    // The spent UTXO is removed once a block including the spend is mined.
+    send_transaction(&env, spending_transaction(&[&spent]));
+    assert_eq!(get_utxos(&env, address0).utxos, before.utxos);
+    mine_blocks(&env, 1);
+    assert_eq!(get_utxos(&env, address0).utxos, vec![kept.clone()]);
+    let created = random_utxo(3, 500);
+    push_utxo(&env, address1, created.clone());
+    assert_eq!(get_address_stats(&env, address0).confirmed_value, 2_000);
+    assert_eq!(get_utxos(&env, address1).utxos, vec![created]);
+
+    // Rolling back the block with the spend restores the state at height 2.
+    reorg(&env, 1);
+    let after = get_utxos(&env, address0);
+    assert_eq!(after.tip_height, 2);
+    assert_eq!(after.utxos, before.utxos);
+    assert_eq!(get_address_stats(&env, address0), stats_before);
+    assert!(get_utxos(&env, address1).utxos.is_empty());
+
+    // Rolling back further removes the UTXOs created in the rolled back blocks.
+    reorg(&env, 2);
+    assert!(get_utxos(&env, address0).utxos.is_empty());
+}
+
+#[test]
+fn test_reorg_keeps_unmined_spends() {
+    let env = setup_bitcoin_mock();
+    let address = "31xxvrZWyZohLR5CKE3wTqur6rbEfi5HUz";
+
+    let spent = random_utxo(1, 1_000);
+    let kept = random_utxo(1, 2_000);
+    push_utxo(&env, address, spent.clone());
+    push_utxo(&env, address, kept.clone());
+    mine_blocks(&env, 3);
+    let sorted_utxos = || {
+        let mut utxos = get_utxos(&env, address).utxos;
+        utxos.sort_by_key(|utxo| utxo.value);
+        utxos
+    };
+
+    // The transaction is sent but no block is mined, so the UTXO it spends is
+    // still returned, also after rolling back blocks.
+    send_transaction(&env, spending_transaction(&[&spent]));
+    assert_eq!(sorted_utxos(), vec![spent.clone(), kept.clone()]);
+    reorg(&env, 0);
+    reorg(&env, 1);
+    assert_eq!(sorted_utxos(), vec![spent.clone(), kept.clone()]);
+
+    // The spend is included in the next mined block, so rolling back that
+    // block reinstates the UTXO.
+    mine_blocks(&env, 1);
+    assert_eq!(sorted_utxos(), vec![kept.clone()]);
+    reorg(&env, 1);
+    assert_eq!(sorted_utxos(), vec![spent, kept]);
+}
+
+fn get_call_counts(env: &StateMachine) -> BTreeMap<String, u64> {
+    Decode!(
+        &env.query(
+            testnet_bitcoin_canister_id(),
+            "get_call_counts",
+            Encode!().unwrap(),
+        )
+        .expect("failed to get the call counts")
+        .bytes(),
+        BTreeMap<String, u64>
+    )
+    .expect("failed to decode get_call_counts response")
+}
+
+#[test]
+fn test_call_counts() {
+    let env = setup_bitcoin_mock();
+    let address = "31xxvrZWyZohLR5CKE3wTqur6rbEfi5HUz";
+
+    assert!(get_call_counts(&env).is_empty());
+
+    // Calls to other endpoints are not counted.
+    push_utxo(&env, address, random_utxo(0, 1_000));
+    mine_blocks(&env, 1);
+    assert!(get_call_counts(&env).is_empty());
+
+    get_utxos(&env, address);
+    get_utxos(&env, address);
+    get_fee_percentiles(&env);
+    send_transaction(&env, vec![1, 2, 3]);
+    // Calls that trap are not counted.
+    get_utxos_with_filter(
+        &env,
+        address,
+        Some(UtxosFilterInRequest::Page(vec![1, 2, 3].into())),
+    )
+    .unwrap_err();
+    assert_eq!(
+        get_call_counts(&env),
+        BTreeMap::from([
+            ("bitcoin_get_current_fee_percentiles".to_string(), 1),
+            ("bitcoin_get_utxos".to_string(), 2),
+            ("bitcoin_send_transaction".to_string(), 1),
+        ])
+    );
+
+    env.execute_ingress(
+        testnet_bitcoin_canister_id(),
+        "reset_call_counts",
+        Encode!().unwrap(),
+    )
+    .expect("failed to reset the call counts");
+    assert!(get_call_counts(&env).is_empty());
+
+    get_utxos(&env, address);
+    assert_eq!(
+        get_call_counts(&env),
+        BTreeMap::from([("bitcoin_get_utxos".to_string(), 1)])
+    );
+}
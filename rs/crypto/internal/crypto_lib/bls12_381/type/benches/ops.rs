@@ -683,24 +683,15 @@ fn bls12_381_batch_sig_verification_multithreaded(c: &mut Criterion) {
                 b.iter_batched(
                     || Arc::new(random_batch_sig_verification_instances(size)),
                     |sigs_pks_msgs| {
-                        let threads = (0..NUM_THREADS).map(|i| {
-                            let size_c = size;
-                            let sigs_pks_msgs_c = sigs_pks_msgs.clone();
-                            std::thread::spawn(move || {
-                                let sigs_pks_msgs_refs = sigs_pks_msgs_c
-                                    [i * size_c / NUM_THREADS..(i + 1) * size_c / NUM_THREADS]
-                                    .iter()
-                                    .map(|(sig, pk, msg)| (sig, pk, msg))
-                                    .collect::<Vec<_>>();
-                                black_box(verify_bls_signature_batch_distinct(
-                                    &sigs_pks_msgs_refs,
-                                    &mut rand::thread_rng(),
-                                ));
-                            })
-                        });
-                        for t in threads {
-                            t.join().unwrap();
-                        }
+                        let sigs_pks_msgs_refs = sigs_pks_msgs
+                            .iter()
+                            .map(|(sig, pk, msg)| (sig, pk, msg))
+                            .collect::<Vec<_>>();
+                        black_box(verify_bls_signature_batch_distinct_parallel(
+                            &sigs_pks_msgs_refs,
+                            NUM_THREADS,
+                            rand::thread_rng,
+                        ));
                     },
                     BatchSize::SmallInput,
                 );
@@ -0,0 +1,57 @@
+//! Implementations of `proptest::arbitrary::Arbitrary`
+//!
+//! Scalars are generated from 32 arbitrary bytes which are reduced modulo
+//! the group order, so every generated value is canonical and shrinking moves
+//! towards zero. Group elements are produced by multiplying the generator by
+//! an arbitrary scalar, so they are always in the prime order subgroup and
+//! shrink towards the identity.
+
+use crate::{G1Affine, G1Projective, G2Affine, G2Projective, Gt, Scalar};
+use proptest::prelude::*;
+
+impl Arbitrary for Scalar {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        any::<[u8; Scalar::BYTES]>()
+            .prop_map(|bytes| Scalar::deserialize_unchecked(&bytes))
+            .boxed()
+    }
+}
+
+macro_rules! impl_arbitrary_for_affine_and_projective {
+    ( $affine:ty, $projective:ty ) => {
+        impl Arbitrary for $projective {
+            type Parameters = ();
+            type Strategy = BoxedStrategy<Self>;
+
+            fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+                any::<Scalar>()
+                    .prop_map(|s| <$affine>::generator() * &s)
+                    .boxed()
+            }
+        }
+
+        impl Arbitrary for $affine {
+            type Parameters = ();
+            type Strategy = BoxedStrategy<Self>;
+
+            fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+                any::<$projective>().prop_map(|p| p.to_affine()).boxed()
+            }
+        }
+    };
+}
+
+impl_arbitrary_for_affine_and_projective!(G1Affine, G1Projective);
+impl_arbitrary_for_affine_and_projective!(G2Affine, G2Projective);
+
+impl Arbitrary for Gt {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        any::<Scalar>().prop_map(|s| Gt::generator() * &s).boxed()
+    }
+}
@@ -9,6 +9,9 @@
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "proptest")]
+mod arbitrary;
+
 use ic_bls12_381::hash_to_curve::{ExpandMsgXmd, HashToCurve};
 use itertools::multiunzip;
 use pairing::group::{ff::Field, Group};
@@ -42,6 +45,60 @@ pub enum PairingInvalidScalar {
     InvalidScalar,
 }
 
+/// Error returned if a batch of BLS signatures is invalid
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BatchError {
+    /// At least one signature of the batch was invalid
+    InvalidBatch,
+}
+
+/// Error returned if two slices which must have the same length do not
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct LengthMismatch {
+    /// The length of the first slice
+    pub lhs: usize,
+    /// The length of the second slice
+    pub rhs: usize,
+}
+
+/// Detailed error returned if a scalar encoding is invalid
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ScalarDeserError {
+    /// The encoding was not exactly `Scalar::BYTES` long
+    WrongLength {
+        /// The length of the provided encoding
+        length: usize,
+    },
+    /// The encoded integer was greater than or equal to the group order
+    OutOfRange,
+}
+
+impl From<ScalarDeserError> for PairingInvalidScalar {
+    fn from(_: ScalarDeserError) -> Self {
+        Self::InvalidScalar
+    }
+}
+
+/// Error returned by [`Scalar::try_from_canonical_bytes`]
+///
+/// Carries the rejected encoding so that it can be logged.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct NonCanonicalScalar {
+    /// The rejected big-endian encoding
+    pub bytes: [u8; Scalar::BYTES],
+    /// Why the encoding was rejected
+    ///
+    /// Since the input has a fixed length this is always
+    /// [`ScalarDeserError::OutOfRange`]
+    pub reason: ScalarDeserError,
+}
+
+impl From<NonCanonicalScalar> for PairingInvalidScalar {
+    fn from(_: NonCanonicalScalar) -> Self {
+        Self::InvalidScalar
+    }
+}
+
 /// An integer of the order of the groups G1/G2/Gt
 #[derive(Clone, Eq, PartialEq, Zeroize, ZeroizeOnDrop)]
 pub struct Scalar {
@@ -76,10 +133,75 @@ macro_rules! impl_debug_using_serialize_for {
 
 impl_debug_using_serialize_for!(Scalar);
 
+/// Formats the scalar as the hex encoding of its big-endian serialization
+///
+/// With the alternate flag (`{:#x}`) the output is prefixed with `0x`
+impl fmt::LowerHex for Scalar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            write!(f, "0x")?;
+        }
+        write!(f, "{}", hex::encode(self.serialize()))
+    }
+}
+
+/// Formats the scalar as the uppercase hex encoding of its big-endian serialization
+///
+/// With the alternate flag (`{:#X}`) the output is prefixed with `0x`
+impl fmt::UpperHex for Scalar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            write!(f, "0x")?;
+        }
+        write!(f, "{}", hex::encode_upper(self.serialize()))
+    }
+}
+
+/// Formats the scalar as a truncated hex encoding, for brevity in logs
+///
+/// Only the first [`Scalar::DISPLAY_PREFIX_BYTES`] bytes of the big-endian
+/// serialization are shown, followed by `...`. Use the `{:x}` format to
+/// print the full value.
+impl fmt::Display for Scalar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes = self.serialize();
+        write!(
+            f,
+            "{}...",
+            hex::encode(&bytes[..Self::DISPLAY_PREFIX_BYTES])
+        )
+    }
+}
+
 impl Scalar {
     /// The size in bytes of this type
     pub const BYTES: usize = 32;
 
+    /// The number of leading bytes shown by the `Display` implementation
+    pub const DISPLAY_PREFIX_BYTES: usize = 8;
+
+    /// The length of the encoding produced by [`Scalar::serialize`]
+    pub const SERIALIZED_SIZE: usize = Self::BYTES;
+
+    /// The order of the groups G1/G2/Gt as a big-endian byte string
+    ///
+    /// This is the value
+    /// 0x73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001
+    ///
+    /// It is not itself a valid encoding of a scalar
+    pub const MODULUS: [u8; Self::BYTES] = [
+        0x73, 0xed, 0xa7, 0x53, 0x29, 0x9d, 0x7d, 0x48, 0x33, 0x39, 0xd8, 0x08, 0x09, 0xa1, 0xd8,
+        0x05, 0x53, 0xbd, 0xa4, 0x02, 0xff, 0xfe, 0x5b, 0xfe, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00,
+        0x00, 0x01,
+    ];
+
+    /// The generator of the multiplicative group of the scalar field
+    ///
+    /// This is the scalar 7, which is also a quadratic non-residue
+    pub const MULTIPLICATIVE_GENERATOR: Self = Self {
+        value: ic_bls12_381::Scalar::from_raw([7, 0, 0, 0]),
+    };
+
     /// Create a new Scalar from the inner type
     pub(crate) fn new(value: ic_bls12_381::Scalar) -> Self {
         Self { value }
@@ -103,10 +225,18 @@ impl Scalar {
 
     /// Create a scalar from a small integer value
     pub fn from_i32(v: i32) -> Self {
+        Self::from_i64(v as i64)
+    }
+
+    /// Create a scalar from a small integer value
+    ///
+    /// Negative values are mapped to the negation of their absolute value,
+    /// so for example `from_i64(-1)` is equal to `-Scalar::one()`
+    pub fn from_i64(v: i64) -> Self {
         if v < 0 {
-            Self::from_u64((v as i64).unsigned_abs()).neg()
+            Self::from_u64(v.unsigned_abs()).neg()
         } else {
-            Self::from_u64(v.unsigned_abs() as u64)
+            Self::from_u64(v.unsigned_abs())
         }
     }
 
@@ -117,11 +247,7 @@ impl Scalar {
 
     /// Create a scalar from a small integer value
     pub fn from_isize(v: isize) -> Self {
-        if v < 0 {
-            Self::from_u64((v as i64).unsigned_abs()).neg()
-        } else {
-            Self::from_u64(v.unsigned_abs() as u64)
-        }
+        Self::from_i64(v as i64)
     }
 
     /// Return `cnt` consecutive powers of `x`
@@ -197,16 +323,135 @@ impl Scalar {
         bool::from(self.value.is_zero())
     }
 
+    /// Return true iff `self - other` is zero
+    ///
+    /// This is useful when computing differences which are later inverted,
+    /// such as the denominators of Lagrange coefficients, to detect equal
+    /// inputs where they occur rather than at the failing inversion.
+    pub fn sub_is_zero(&self, other: &Self) -> bool {
+        bool::from((self.value - other.value).is_zero())
+    }
+
     /// Return the additive inverse of this scalar
     pub fn neg(&self) -> Self {
         Self::new(self.value.neg())
     }
 
+    /// Precompute the recoding of this scalar for multiplying many points by it
+    ///
+    /// See [`ScalarMulTable`]
+    pub fn precompute_mul(&self) -> ScalarMulTable {
+        ScalarMulTable::new(self)
+    }
+
+    /// Replace each scalar of the slice with its additive inverse
+    pub fn negate_slice(scalars: &mut [Self]) {
+        for s in scalars {
+            s.value = s.value.neg();
+        }
+    }
+
+    /// Return the additive inverses of a slice of scalars
+    pub fn negate_to_vec(scalars: &[Self]) -> Vec<Self> {
+        scalars.iter().map(|s| s.neg()).collect()
+    }
+
+    /// Return the element-wise products of two slices of scalars
+    ///
+    /// Returns an error if the slices have different lengths
+    pub fn hadamard(a: &[Self], b: &[Self]) -> Result<Vec<Self>, LengthMismatch> {
+        if a.len() != b.len() {
+            return Err(LengthMismatch {
+                lhs: a.len(),
+                rhs: b.len(),
+            });
+        }
+        Ok(a.iter().zip(b.iter()).map(|(x, y)| x * y).collect())
+    }
+
+    /// Multiply each scalar of `a` by the scalar at the same index of `b`
+    ///
+    /// Returns an error, leaving `a` unmodified, if the slices have different
+    /// lengths
+    pub fn hadamard_assign(a: &mut [Self], b: &[Self]) -> Result<(), LengthMismatch> {
+        if a.len() != b.len() {
+            return Err(LengthMismatch {
+                lhs: a.len(),
+                rhs: b.len(),
+            });
+        }
+        for (x, y) in a.iter_mut().zip(b.iter()) {
+            *x *= y;
+        }
+        Ok(())
+    }
+
+    /// Return true iff this scalar is greater than (order-1)/2
+    ///
+    /// Exactly one of `s` and `-s` is high for any non-zero `s`; zero is
+    /// not high. The comparison runs in constant time.
+    pub fn is_high(&self) -> bool {
+        // (order-1)/2 = 0x39f6d3a994cebea4199cec0404d0ec02a9ded2017fff2dff7fffffff80000000
+        const HALF_ORDER: Scalar = Scalar {
+            value: ic_bls12_381::Scalar::from_raw([
+                0x7fffffff80000000,
+                0xa9ded2017fff2dff,
+                0x199cec0404d0ec02,
+                0x39f6d3a994cebea4,
+            ]),
+        };
+
+        self.ct_compare(&HALF_ORDER) > 0
+    }
+
+    /// Return the low representative of `{self, -self}`
+    ///
+    /// Returns `(-self, true)` if this scalar is high (see [`Scalar::is_high`])
+    /// and `(self, false)` otherwise, so the returned scalar is never high.
+    pub fn canonical_signed(&self) -> (Self, bool) {
+        let is_high = subtle::Choice::from(self.is_high() as u8);
+        (
+            Self::conditional_select(self, &self.neg(), is_high),
+            bool::from(is_high),
+        )
+    }
+
+    /// Constant time selection
+    ///
+    /// Returns `a` if `choice` is 0 and `b` if `choice` is 1, without
+    /// branching on `choice`. This mirrors
+    /// `subtle::ConditionallySelectable`, which cannot be implemented
+    /// since that trait requires `Copy`.
+    pub fn conditional_select(a: &Self, b: &Self, choice: subtle::Choice) -> Self {
+        use subtle::ConditionallySelectable;
+        Self::new(ic_bls12_381::Scalar::conditional_select(
+            &a.value, &b.value, choice,
+        ))
+    }
+
+    /// Constant time assignment
+    ///
+    /// Sets `self` to `other` if `choice` is 1 and leaves it unchanged if
+    /// `choice` is 0, without branching on `choice`.
+    pub fn conditional_assign(&mut self, other: &Self, choice: subtle::Choice) {
+        use subtle::ConditionallySelectable;
+        self.value.conditional_assign(&other.value, choice);
+    }
+
     /// Double this scalar
     pub fn double(&self) -> Self {
         Self::new(self.value.double())
     }
 
+    /// Return a square root of this scalar, if one exists
+    ///
+    /// Returns None if this scalar is a quadratic non-residue. Otherwise
+    /// returns a value whose square is this scalar; which of the two roots
+    /// is returned is unspecified.
+    pub fn sqrt(&self) -> subtle::CtOption<Self> {
+        self.value.sqrt().map(Self::new)
+    }
+
     /// Return the multiplicative inverse of this scalar if it exists
     pub fn inverse(&self) -> Option<Self> {
         let inv = self.value.invert();
@@ -217,6 +462,14 @@ impl Scalar {
         }
     }
 
+    /// Return `self` divided by `other`, or None if `other` is zero
+    ///
+    /// This is the preferred way to divide scalars. The `/` operator is
+    /// equivalent but panics if the divisor is zero.
+    pub fn checked_div(&self, other: &Self) -> Option<Self> {
+        other.inverse().map(|inv| self * inv)
+    }
+
     /// Return a random scalar
     pub fn random<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
         loop {
@@ -236,6 +489,26 @@ impl Scalar {
         }
     }
 
+    /// Deterministically derive a scalar from a domain label and an index
+    ///
+    /// The scalar is SHA-512 of the length-prefixed domain and the big-endian
+    /// index, read as a little-endian integer and reduced modulo the group
+    /// order. This is intended for producing stable, labeled values in tests
+    /// and test vectors. Anyone who knows the label can recompute the scalar,
+    /// so it must not be used to create secret keys.
+    pub fn from_seed(domain: &[u8], index: u32) -> Self {
+        use sha2::Digest;
+
+        let mut hasher = sha2::Sha512::new();
+        hasher.update((domain.len() as u64).to_be_bytes());
+        hasher.update(domain);
+        hasher.update(index.to_be_bytes());
+
+        let mut wide = [0u8; 64];
+        wide.copy_from_slice(&hasher.finalize());
+        Self::new(ic_bls12_381::Scalar::from_bytes_wide(&wide))
+    }
+
     /// Randomly sample exactly `amount` unbiased indices from `0..range` using an inplace partial Fisher-Yates method.
     ///
     /// This method is adopted from the std::rand crate including some comments, see
@@ -373,14 +646,35 @@ impl Scalar {
 
     /// Deserialize a scalar from a big-endian byte string
     pub fn deserialize<B: AsRef<[u8]>>(bytes: &B) -> Result<Self, PairingInvalidScalar> {
-        let mut bytes: [u8; Self::BYTES] = bytes
-            .as_ref()
-            .try_into()
-            .map_err(|_| PairingInvalidScalar::InvalidScalar)?;
+        Ok(Self::deserialize_detailed(bytes.as_ref())?)
+    }
+
+    /// Deserialize a scalar from a big-endian byte string
+    ///
+    /// Unlike [`Scalar::deserialize`], the returned error distinguishes
+    /// an encoding of the wrong length from one which is of the correct
+    /// length but encodes an integer that is not less than the group order.
+    /// Since the encoding is fixed length, every in-range integer has exactly
+    /// one encoding, so there is no separate non-canonical case.
+    pub fn deserialize_detailed(bytes: &[u8]) -> Result<Self, ScalarDeserError> {
+        let mut bytes: [u8; Self::BYTES] =
+            bytes
+                .try_into()
+                .map_err(|_| ScalarDeserError::WrongLength {
+                    length: bytes.len(),
+                })?;
         bytes.reverse();
         let scalar = ic_bls12_381::Scalar::from_bytes(&bytes);
         bytes.zeroize();
-        ctoption_ok_or!(scalar, PairingInvalidScalar::InvalidScalar)
+        ctoption_ok_or!(scalar, ScalarDeserError::OutOfRange)
+    }
+
+    /// Deserialize a scalar from a big-endian byte string
+    ///
+    /// Equivalent to [`Scalar::deserialize_detailed`], except that the
+    /// returned error includes the rejected bytes.
+    pub fn try_from_canonical_bytes(bytes: [u8; Self::BYTES]) -> Result<Self, NonCanonicalScalar> {
+        Self::deserialize_detailed(&bytes).map_err(|reason| NonCanonicalScalar { bytes, reason })
     }
 
     /// Deserialize multiple scalars
@@ -413,12 +707,85 @@ impl Scalar {
     }
 
     /// Serialize the scalar to a big-endian byte string
-    pub fn serialize(&self) -> [u8; Self::BYTES] {
+    pub fn serialize(&self) -> [u8; Self::SERIALIZED_SIZE] {
         let mut bytes = self.value.to_bytes();
         bytes.reverse();
         bytes
     }
 
+    /// Return the 64-bit limbs of the scalar, least significant first
+    ///
+    /// The limbs are those of the canonical integer in `[0, order)`, not of
+    /// the Montgomery form used internally, so the value of the scalar is
+    /// `d[0] + d[1]*2^64 + d[2]*2^128 + d[3]*2^192`
+    pub fn to_u64_digits(&self) -> [u64; 4] {
+        let mut bytes = self.value.to_bytes();
+        let mut digits = [0u64; 4];
+        for (digit, chunk) in digits.iter_mut().zip(bytes.chunks_exact(8)) {
+            *digit = u64::from_le_bytes(chunk.try_into().expect("chunk is 8 bytes"));
+        }
+        bytes.zeroize();
+        digits
+    }
+
+    /// Create a scalar from its 64-bit limbs, least significant first
+    ///
+    /// This is the inverse of [`Scalar::to_u64_digits`]. Returns
+    /// [`ScalarDeserError::OutOfRange`] if the integer
+    /// `d[0] + d[1]*2^64 + d[2]*2^128 + d[3]*2^192` is not less than the
+    /// group order.
+    pub fn from_u64_digits(digits: [u64; 4]) -> Result<Self, ScalarDeserError> {
+        let mut bytes = [0u8; Self::BYTES];
+        for (chunk, digit) in bytes.chunks_exact_mut(8).zip(digits.iter()) {
+            chunk.copy_from_slice(&digit.to_le_bytes());
+        }
+        let scalar = ic_bls12_381::Scalar::from_bytes(&bytes);
+        bytes.zeroize();
+        ctoption_ok_or!(scalar, ScalarDeserError::OutOfRange)
+    }
+
+    /// Serialize the scalar to a big-endian byte string of exactly `width` bytes
+    ///
+    /// If `width` is larger than [`Scalar::BYTES`] the encoding is left-padded
+    /// with zeros. Smaller widths are accepted as long as the value fits,
+    /// otherwise None is returned.
+    pub fn to_padded_bytes(&self, width: usize) -> Option<Vec<u8>> {
+        let bytes = self.serialize();
+        let leading_zeros = bytes.iter().take_while(|b| **b == 0).count();
+        if Self::BYTES - leading_zeros > width {
+            return None;
+        }
+
+        let mut result = vec![0u8; width];
+        if width >= Self::BYTES {
+            result[width - Self::BYTES..].copy_from_slice(&bytes);
+        } else {
+            result.copy_from_slice(&bytes[Self::BYTES - width..]);
+        }
+        Some(result)
+    }
+
+    /// Deserialize a scalar from a big-endian byte string of any length
+    ///
+    /// This is the inverse of [`Scalar::to_padded_bytes`]. Inputs shorter than
+    /// [`Scalar::BYTES`] are treated as if left-padded with zeros. Inputs
+    /// which are longer must have only zeros in the excess leading bytes.
+    pub fn from_padded_bytes(bytes: &[u8]) -> Result<Self, ScalarDeserError> {
+        let mut buf = [0u8; Self::BYTES];
+        if bytes.len() >= Self::BYTES {
+            let (padding, value) = bytes.split_at(bytes.len() - Self::BYTES);
+            if padding.iter().any(|b| *b != 0) {
+                return Err(ScalarDeserError::OutOfRange);
+            }
+            buf.copy_from_slice(value);
+        } else {
+            buf[Self::BYTES - bytes.len()..].copy_from_slice(bytes);
+        }
+        let result = Self::deserialize_detailed(&buf);
+        buf.zeroize();
+        result
+    }
+
     /// Serialize the scalar to a big-endian byte string in some specific type
     pub fn serialize_to<T: From<[u8; Self::BYTES]>>(&self) -> T {
         T::from(self.serialize())
@@ -681,6 +1048,35 @@ macro_rules! declare_mixed_addition_ops_for {
     };
 }
 
+macro_rules! declare_from_iterator_for {
+    ( $proj:ty, $affine:ty ) => {
+        /// Collects points into their sum; an empty iterator yields the identity
+        impl std::iter::FromIterator<$proj> for $proj {
+            fn from_iter<I: IntoIterator<Item = $proj>>(iter: I) -> Self {
+                let mut sum = <$proj>::identity();
+                for pt in iter {
+                    sum += pt;
+                }
+                sum
+            }
+        }
+
+        /// Collects points into their sum; an empty iterator yields the identity
+        ///
+        /// The points are added using mixed addition, which is cheaper than
+        /// adding projective points
+        impl std::iter::FromIterator<$affine> for $proj {
+            fn from_iter<I: IntoIterator<Item = $affine>>(iter: I) -> Self {
+                let mut sum = <$proj>::identity();
+                for pt in iter {
+                    sum += pt;
+                }
+                sum
+            }
+        }
+    };
+}
+
 macro_rules! declare_mul_scalar_ops_for {
     ( $typ:ty ) => {
         impl std::ops::Mul<&Scalar> for &$typ {
@@ -728,6 +1124,79 @@ macro_rules! declare_mul_scalar_ops_for {
 declare_addsub_ops_for!(Scalar);
 declare_mul_scalar_ops_for!(Scalar);
 
+/// Divides two scalars
+///
+/// # Panics
+///
+/// Panics if the divisor is zero; use [`Scalar::checked_div`] if the
+/// divisor may be zero.
+impl std::ops::Div<&Scalar> for &Scalar {
+    type Output = Scalar;
+    fn div(self, other: &Scalar) -> Scalar {
+        self.checked_div(other)
+            .expect("Division of a scalar by zero")
+    }
+}
+
+impl std::ops::Div<&Scalar> for Scalar {
+    type Output = Scalar;
+    fn div(self, other: &Scalar) -> Scalar {
+        &self / other
+    }
+}
+
+impl std::ops::Div<Scalar> for &Scalar {
+    type Output = Scalar;
+    fn div(self, other: Scalar) -> Scalar {
+        self / &other
+    }
+}
+
+impl std::ops::Div for Scalar {
+    type Output = Scalar;
+    fn div(self, other: Scalar) -> Scalar {
+        &self / &other
+    }
+}
+
+impl std::ops::Neg for &Scalar {
+    type Output = Scalar;
+    fn neg(self) -> Scalar {
+        Scalar::neg(self)
+    }
+}
+
+impl std::ops::Neg for Scalar {
+    type Output = Scalar;
+    fn neg(self) -> Scalar {
+        Scalar::neg(&self)
+    }
+}
+
+impl std::iter::Sum for Scalar {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |accum, s| accum + s)
+    }
+}
+
+impl<'a> std::iter::Sum<&'a Scalar> for Scalar {
+    fn sum<I: Iterator<Item = &'a Scalar>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |accum, s| accum + s)
+    }
+}
+
+impl std::iter::Product for Scalar {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::one(), |accum, s| accum * s)
+    }
+}
+
+impl<'a> std::iter::Product<&'a Scalar> for Scalar {
+    fn product<I: Iterator<Item = &'a Scalar>>(iter: I) -> Self {
+        iter.fold(Self::one(), |accum, s| accum * s)
+    }
+}
+
 macro_rules! define_affine_and_projective_types {
     ( $affine:ident, $projective:ident, $size:expr ) => {
         paste! {
@@ -946,6 +1415,14 @@ macro_rules! define_affine_and_projective_types {
             /// The size in bytes of this type
             pub const BYTES: usize = $size;
 
+            /// The length of the compressed encoding produced by `serialize`
+            pub const SERIALIZED_SIZE: usize = Self::BYTES;
+
+            /// The length of the uncompressed encoding produced by
+            /// `serialize_uncompressed`, also used for the table elements of
+            /// `serialize_with_precompute`
+            pub const UNCOMPRESSED_SIZE: usize = 2 * Self::BYTES;
+
             /// Create a struct from the inner type
             pub(crate) fn new(value: ic_bls12_381::$affine) -> Self {
                 Self { value, precomputed: None }
@@ -993,9 +1470,18 @@ macro_rules! define_affine_and_projective_types {
                 paste! { &[<$affine:upper _GENERATOR>] }
             }
 
+            /// Convert a group of points from projective to affine format
+            ///
+            /// This is the same as `batch_normalize` of the projective type,
+            /// and is much faster than calling `to_affine` on each point, as
+            /// a single field inversion is shared by all of the points.
+            pub fn batch_from_projective(points: &[$projective]) -> Vec<Self> {
+                $projective::batch_normalize(points)
+            }
+
             /// Hash into the group
             ///
-            /// This follows draft-irtf-cfrg-hash-to-curve-16 using the
+            /// This follows RFC 9380 (draft-irtf-cfrg-hash-to-curve-16) using the
             /// BLS12381G1_XMD:SHA-256_SSWU_RO_ or
             /// BLS12381G2_XMD:SHA-256_SSWU_RO_ suite.
             ///
@@ -1008,7 +1494,7 @@ macro_rules! define_affine_and_projective_types {
 
             /// Hash into the group, returning a point with precomputations
             ///
-            /// This follows draft-irtf-cfrg-hash-to-curve-16 using the
+            /// This follows RFC 9380 (draft-irtf-cfrg-hash-to-curve-16) using the
             /// BLS12381G1_XMD:SHA-256_SSWU_RO_ or
             /// BLS12381G2_XMD:SHA-256_SSWU_RO_ suite.
             ///
@@ -1075,11 +1561,73 @@ macro_rules! define_affine_and_projective_types {
                 ctoption_ok_or!(pt, PairingInvalidPoint::InvalidPoint)
             }
 
+            /// Return a random point which is on the curve but not within the
+            /// prime-order subgroup
+            ///
+            /// Such points can only be created using the unchecked deserialization
+            /// routines; this is intended for negative tests of subgroup checks.
+            #[cfg(any(test, feature = "test-utils"))]
+            pub fn random_not_in_subgroup<R: RngCore>(rng: &mut R) -> Self {
+                loop {
+                    let mut bytes = [0u8; Self::BYTES];
+                    rng.fill_bytes(&mut bytes);
+                    // Clear the top bits of each (48 byte) field element so that it
+                    // is usually less than the modulus, then set the compression flag
+                    // and a random sort flag. The infinity flag remains cleared.
+                    for fe in bytes.chunks_mut(48) {
+                        fe[0] &= 0x1f;
+                    }
+                    bytes[0] |= 0x80 | if rng.gen::<bool>() { 0x20 } else { 0 };
+                    if let Ok(pt) = Self::deserialize_unchecked(&bytes) {
+                        if !pt.is_torsion_free() {
+                            return pt;
+                        }
+                    }
+                }
+            }
+
             /// Serialize this point in compressed format
-            pub fn serialize(&self) -> [u8; Self::BYTES] {
+            pub fn serialize(&self) -> [u8; Self::SERIALIZED_SIZE] {
                 self.value.to_compressed()
             }
 
+            /// Serialize this point in uncompressed format
+            ///
+            /// The encoding is the x coordinate followed by the y coordinate,
+            /// with the flag bits set as in the ZCash encoding (the compression
+            /// flag is clear)
+            pub fn serialize_uncompressed(&self) -> [u8; Self::UNCOMPRESSED_SIZE] {
+                self.value.to_uncompressed()
+            }
+
+            /// Deserialize a point (uncompressed format only)
+            ///
+            /// This version verifies that the decoded point is on the curve and
+            /// within the prime order subgroup, and is safe to call on untrusted
+            /// inputs.
+            pub fn deserialize_uncompressed<B: AsRef<[u8]>>(bytes: &B) -> Result<Self, PairingInvalidPoint> {
+                let bytes : &[u8; Self::UNCOMPRESSED_SIZE] = bytes.as_ref()
+                    .try_into()
+                    .map_err(|_| PairingInvalidPoint::InvalidPoint)?;
+                let pt = ic_bls12_381::$affine::from_uncompressed(bytes);
+                ctoption_ok_or!(pt, PairingInvalidPoint::InvalidPoint)
+            }
+
+            /// Deserialize a point (uncompressed format only), trusted bytes edition
+            ///
+            /// This version verifies that the decoded point is on the curve, but
+            /// not that it is within the prime-order subgroup. This can be
+            /// detected by calling is_torsion_free
+            pub fn deserialize_uncompressed_unchecked<B: AsRef<[u8]>>(bytes: &B) -> Result<Self, PairingInvalidPoint> {
+                let bytes : &[u8; Self::UNCOMPRESSED_SIZE] = bytes.as_ref()
+                    .try_into()
+                    .map_err(|_| PairingInvalidPoint::InvalidPoint)?;
+                // from_uncompressed_unchecked does not check that the point is on the curve
+                let pt = ic_bls12_381::$affine::from_uncompressed_unchecked(bytes)
+                    .and_then(|pt| subtle::CtOption::new(pt, pt.is_on_curve()));
+                ctoption_ok_or!(pt, PairingInvalidPoint::InvalidPoint)
+            }
+
             /// Serialize a point in compressed format in some specific type
             pub fn serialize_to<T: From<[u8; Self::BYTES]>>(&self) -> T {
                 T::from(self.serialize())
@@ -1102,6 +1650,79 @@ macro_rules! define_affine_and_projective_types {
                 result
             }
 
+            /// Serialize this point along with its multiplication table
+            ///
+            /// The encoding is the compressed point followed by each element
+            /// of the precomputed table in uncompressed format. If the point
+            /// does not yet have a table, one is computed first.
+            ///
+            /// Use deserialize_with_precompute to restore the point
+            pub fn serialize_with_precompute(&self) -> Vec<u8> {
+                type Tbl = paste! { [<$affine PrecomputedTable>] };
+
+                let computed;
+                let tbl = match self.precomputed {
+                    Some(ref tbl) => tbl.as_ref(),
+                    None => {
+                        computed = Tbl::new(self);
+                        &computed
+                    }
+                };
+
+                let mut result = Vec::with_capacity(Self::SERIALIZED_SIZE + Self::UNCOMPRESSED_SIZE * Tbl::TABLE_SIZE);
+                result.extend_from_slice(&self.serialize());
+                for elem in &tbl.tbl {
+                    result.extend_from_slice(elem.to_uncompressed().as_ref());
+                }
+                result
+            }
+
+            /// Deserialize a point along with its multiplication table
+            ///
+            /// This accepts the output of serialize_with_precompute. The point
+            /// itself is checked as in deserialize, and each table element is
+            /// checked to be on the curve, but the table is not otherwise
+            /// verified to be consistent with the point (doing so would cost as
+            /// much as recomputing it). A corrupted table results in incorrect
+            /// multiplication results, so this function must only be used on
+            /// bytes read back from trusted storage.
+            pub fn deserialize_with_precompute(bytes: &[u8]) -> Result<Self, PairingInvalidPoint> {
+                type Tbl = paste! { [<$affine PrecomputedTable>] };
+
+                const UNCOMPRESSED_BYTES: usize = $affine::UNCOMPRESSED_SIZE;
+
+                if bytes.len() != Self::SERIALIZED_SIZE + UNCOMPRESSED_BYTES * Tbl::TABLE_SIZE {
+                    return Err(PairingInvalidPoint::InvalidPoint);
+                }
+
+                let (pt, tbl_bytes) = bytes.split_at(Self::SERIALIZED_SIZE);
+                let mut pt = Self::deserialize(&pt)?;
+
+                let mut tbl = Vec::with_capacity(Tbl::TABLE_SIZE);
+                for elem in tbl_bytes.chunks_exact(UNCOMPRESSED_BYTES) {
+                    let elem: &[u8; UNCOMPRESSED_BYTES] = elem
+                        .try_into()
+                        .expect("chunks_exact returns chunks of the requested size");
+                    // from_uncompressed_unchecked does not check that the point is on the curve
+                    let elem = ic_bls12_381::$affine::from_uncompressed_unchecked(elem)
+                        .and_then(|pt| subtle::CtOption::new(pt, pt.is_on_curve()));
+                    tbl.push(Option::<ic_bls12_381::$affine>::from(elem).ok_or(PairingInvalidPoint::InvalidPoint)?);
+                }
+
+                // The first table element is always the point itself
+                if tbl[0] != pt.value {
+                    return Err(PairingInvalidPoint::InvalidPoint);
+                }
+
+                pt.precomputed = Some(Arc::new(Tbl { tbl }));
+                Ok(pt)
+            }
+
+            /// Return true if this point has a precomputed multiplication table
+            pub fn has_precomputation(&self) -> bool {
+                self.precomputed.is_some()
+            }
+
             /// Return true if this is the identity element
             pub fn is_identity(&self) -> bool {
                 bool::from(self.value.is_identity())
@@ -1185,9 +1806,55 @@ macro_rules! define_affine_and_projective_types {
                 Self::new(val)
             }
 
+            /// Constant time selection
+            ///
+            /// Returns `a` if `choice` is 0 and `b` if `choice` is 1, without
+            /// branching on `choice`. See [`Scalar::conditional_select`].
+            pub fn conditional_select(a: &Self, b: &Self, choice: subtle::Choice) -> Self {
+                use subtle::ConditionallySelectable;
+                Self::new(ic_bls12_381::$projective::conditional_select(&a.value, &b.value, choice))
+            }
+
+            /// Constant time assignment
+            ///
+            /// Sets `self` to `other` if `choice` is 1 and leaves it unchanged
+            /// if `choice` is 0, without branching on `choice`.
+            pub fn conditional_assign(&mut self, other: &Self, choice: subtle::Choice) {
+                use subtle::ConditionallySelectable;
+                self.value.conditional_assign(&other.value, choice);
+            }
+
             /// Return the doubling of this point
             pub fn double(&self) -> Self {
-                Self::new(self.value.double())
+                let mut r = self.clone();
+                r.double_in_place();
+                r
+            }
+
+            /// Replace this point with its doubling
+            pub fn double_in_place(&mut self) {
+                self.value = self.value.double();
+            }
+
+            /// Multiply this point by a small integer
+            ///
+            /// This is a double-and-add over the 64 bits of `n`, which is
+            /// cheaper than converting `n` to a Scalar and using the general
+            /// multiplication. It runs in constant time with respect to `n`.
+            pub fn mul_u64(&self, n: u64) -> Self {
+                use subtle::ConditionallySelectable;
+                let mut r = ic_bls12_381::$projective::identity();
+
+                for b in 0..64 {
+                    if b > 0 {
+                        r = r.double();
+                    }
+
+                    let choice = subtle::Choice::from(((n >> (63 - b)) as u8) & 1);
+                    r.conditional_assign(&(r + self.value), choice);
+                }
+
+                Self::new(r)
             }
 
             /// Sum some points
@@ -1248,9 +1915,17 @@ macro_rules! define_affine_and_projective_types {
                 paste! { &[<$projective:upper _GENERATOR>] }
             }
 
+            /// Return a uniformly random element of the prime-order subgroup
+            ///
+            /// The point is computed as the generator multiplied by a
+            /// random scalar, so is_torsion_free always holds for the result.
+            pub fn random<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+                $affine::generator() * Scalar::random(rng)
+            }
+
             /// Hash into the group
             ///
-            /// This follows draft-irtf-cfrg-hash-to-curve-16 using the
+            /// This follows RFC 9380 (draft-irtf-cfrg-hash-to-curve-16) using the
             /// BLS12381G1_XMD:SHA-256_SSWU_RO_ or
             /// BLS12381G2_XMD:SHA-256_SSWU_RO_ suite.
             ///
@@ -1267,7 +1942,26 @@ macro_rules! define_affine_and_projective_types {
 
             /// Return true if this is the identity element
             pub fn is_identity(&self) -> bool {
-                bool::from(self.value.is_identity())
+                bool::from(self.ct_is_identity())
+            }
+
+            /// Return if this is the identity element, in constant time
+            ///
+            /// Only the Z coordinate is examined, so any projective
+            /// representation of the identity is recognized regardless
+            /// of the value of X and Y
+            pub fn ct_is_identity(&self) -> subtle::Choice {
+                self.value.is_identity()
+            }
+
+            /// Return true if this point is equal to the affine point `other`
+            ///
+            /// This is equivalent to `self.to_affine() == *other` but avoids
+            /// the field inversion required for normalization; instead the
+            /// coordinates are compared by cross-multiplying with the Z
+            /// coordinate.
+            pub fn eq_affine(&self, other: &$affine) -> bool {
+                self.value == ic_bls12_381::$projective::from(other.inner())
             }
 
             /// Return the inverse of this point
@@ -1276,6 +1970,14 @@ macro_rules! define_affine_and_projective_types {
                 Self::new(self.value.neg())
             }
 
+            /// Replace this point with its inverse
+            ///
+            /// This negates the y coordinate without allocating a new point
+            pub fn negate(&mut self) {
+                use std::ops::Neg;
+                self.value = self.value.neg();
+            }
+
             /// Convert this point to affine format
             pub fn to_affine(&self) -> $affine {
                 $affine::new(self.value.into())
@@ -1374,7 +2076,7 @@ macro_rules! declare_mul2_table_impl {
         /// Table for storing linear combinations of two points.
         /// It is stored as a vector to reduce the amount of indirection for accessing cells.
         /// A table can be computed by calling the `compute_mul2_tbl` function of the corresponding
-        /// projective `struct`, e.g., `G2Projective::mul2_prepared(...)`.
+        /// projective `struct`, e.g., `G2Projective::compute_mul2_tbl(...)`.
         impl $tbl_typ {
             // Compute the column offset in the vector from the column index.
             pub(crate) fn col(i: usize) -> usize {
@@ -1433,6 +2135,8 @@ macro_rules! declare_mul2_table_impl {
 
             #[allow(dead_code)]
             /// Perform a sequence of sum-of-2-products operations and return the results
+            ///
+            /// The i-th result is equal to `self.mul2(&a[i], &b[i])`
             pub fn mul2_array<const N: usize>(
                 &self,
                 a: &[Scalar; N],
@@ -1500,10 +2204,14 @@ macro_rules! declare_mul2_impl_for {
             pub(crate) struct [< Small $tbl_typ >](Vec<$projective>);
             declare_mul2_table_impl!($projective, [< Small $tbl_typ >], $small_window_size);
 
-            /// Contains a small precomputed table with linear combinations of two points that
+            /// Contains a large precomputed table with linear combinations of two points that
             /// can be used for faster mul2 computation. This table is called large because
             /// its parameters are optimized for the best trade-off for pre-computing the table
             /// once and using it for multiplication of the points with multiple scalar pairs.
+            ///
+            /// The table is created with `compute_mul2_tbl` of the corresponding projective
+            /// type, and `tbl.mul2(a, b)` returns the same result as `mul2(x, a, y, b)` for
+            /// the points `x` and `y` that the table was computed from.
             /// For further information, see the rustdoc of `mul2` and `compute_mul2_tbl`.
             pub struct $tbl_typ(Vec<$projective>);
             declare_mul2_table_impl!($projective, $tbl_typ, $big_window_size);
@@ -1684,6 +2392,32 @@ macro_rules! declare_muln_vartime_affine_impl_for {
                 Self::muln_vartime(&proj_points[..], scalars)
             }
 
+            /// Sum of a sequence of two-term multiplications
+            ///
+            /// Equivalent to summing `mul2(x, a, y, b)` over each `(x, a, y, b)`
+            /// in `terms`, but all of the products are computed together in a
+            /// single multiscalar multiplication rather than one `mul2` call
+            /// per term.
+            ///
+            /// Returns the identity element if terms is empty.
+            ///
+            /// Warning: unlike `mul2`, this function leaks information about
+            /// the scalars via memory-based side channels. Do not use this
+            /// function with secret scalars.
+            pub fn mul2_sum(terms: &[($affine, Scalar, $affine, Scalar)]) -> Self {
+                let mut points = Vec::with_capacity(2 * terms.len());
+                let mut scalars = Vec::with_capacity(2 * terms.len());
+
+                for (x, a, y, b) in terms {
+                    points.push(<$proj>::from(x));
+                    scalars.push(a.clone());
+                    points.push(<$proj>::from(y));
+                    scalars.push(b.clone());
+                }
+
+                Self::muln_vartime(&points[..], &scalars[..])
+            }
+
             /// Multiplies and adds together `points` and `scalars` as
             /// `points[0] * scalars[0] + ... + points[l] * scalars[l]`,
             /// where `l` is `min(points.len(), scalars.len())`.
@@ -1725,15 +2459,52 @@ macro_rules! declare_windowed_scalar_mul_ops_for {
     ( $typ:ty, $window:expr ) => {
         impl $typ {
             pub(crate) fn windowed_mul(&self, scalar: &Scalar) -> Self {
-                // Configurable window size: can be in 1..=8
-                type Window = WindowInfo<$window>;
+                self.windowed_mul_with::<$window>(scalar)
+            }
+
+            /// Multiply this element by a scalar using a window of `window_bits` bits
+            ///
+            /// The result is the same as `self * scalar`, which uses a fixed
+            /// window size; this function exists to allow measuring the
+            /// performance of other window sizes.
+            ///
+            /// # Panics
+            ///
+            /// Panics if `window_bits` is not in `1..=8`
+            pub fn mul_windowed(&self, scalar: &Scalar, window_bits: u8) -> Self {
+                match window_bits {
+                    1 => self.windowed_mul_with::<1>(scalar),
+                    2 => self.windowed_mul_with::<2>(scalar),
+                    3 => self.windowed_mul_with::<3>(scalar),
+                    4 => self.windowed_mul_with::<4>(scalar),
+                    5 => self.windowed_mul_with::<5>(scalar),
+                    6 => self.windowed_mul_with::<6>(scalar),
+                    7 => self.windowed_mul_with::<7>(scalar),
+                    8 => self.windowed_mul_with::<8>(scalar),
+                    _ => panic!("Unsupported window size {}, must be in 1..=8", window_bits),
+                }
+            }
+
+            fn windowed_mul_with<const WINDOW_SIZE: usize>(&self, scalar: &Scalar) -> Self {
+                let s = scalar.serialize();
+                self.windowed_mul_with_windows::<WINDOW_SIZE>(|i| {
+                    WindowInfo::<WINDOW_SIZE>::extract(&s, i)
+                })
+            }
 
-                // Derived constants
-                const TABLE_SIZE: usize = Window::ELEMENTS;
+            /// Multiply by the scalar whose `i`th window is `window(i)`
+            ///
+            /// This allows reusing the windows of a scalar, see `ScalarMulTable`
+            pub(crate) fn windowed_mul_with_windows<const WINDOW_SIZE: usize>(
+                &self,
+                window: impl Fn(usize) -> u8,
+            ) -> Self {
+                // WINDOW_SIZE can be in 1..=8
+                let table_size = WindowInfo::<WINDOW_SIZE>::ELEMENTS;
 
-                let mut tbl = Self::identities(TABLE_SIZE);
+                let mut tbl = Self::identities(table_size);
 
-                for i in 1..TABLE_SIZE {
+                for i in 1..table_size {
                     tbl[i] = if i % 2 == 0 {
                         tbl[i / 2].double()
                     } else {
@@ -1741,19 +2512,17 @@ macro_rules! declare_windowed_scalar_mul_ops_for {
                     };
                 }
 
-                let s = scalar.serialize();
-
                 let mut accum = Self::identity();
 
-                for i in 0..Window::WINDOWS {
+                for i in 0..WindowInfo::<WINDOW_SIZE>::WINDOWS {
                     // skip on first iteration: doesn't leak secrets as index is public
                     if i > 0 {
-                        for _ in 0..Window::SIZE {
+                        for _ in 0..WindowInfo::<WINDOW_SIZE>::SIZE {
                             accum = accum.double();
                         }
                     }
 
-                    let w = Window::extract(&s, i);
+                    let w = window(i);
                     accum += Self::ct_select(&tbl, w as usize);
                 }
 
@@ -1816,10 +2585,15 @@ const G1_PROJECTIVE_USE_W3_LARGER_THAN: usize = 12;
 const G1_PROJECTIVE_USE_W4_LARGER_THAN: usize = 64;
 const G2_PROJECTIVE_USE_W3_LARGER_THAN: usize = 8;
 const G2_PROJECTIVE_USE_W4_LARGER_THAN: usize = 64;
+// Gt additions and doublings have similar relative costs to those of G2,
+// so the same cutoffs are used
+const GT_USE_W3_LARGER_THAN: usize = 8;
+const GT_USE_W4_LARGER_THAN: usize = 64;
 
 define_affine_and_projective_types!(G1Affine, G1Projective, 48);
 declare_addsub_ops_for!(G1Projective);
 declare_mixed_addition_ops_for!(G1Projective, G1Affine);
+declare_from_iterator_for!(G1Projective, G1Affine);
 declare_windowed_scalar_mul_ops_for!(G1Projective, 4);
 declare_mul2_impl_for!(G1Projective, G1Mul2Table, 2, 3);
 declare_muln_vartime_dispatch_for!(
@@ -1832,9 +2606,35 @@ declare_muln_vartime_affine_impl_for!(G1Projective, G1Affine);
 impl_debug_using_serialize_for!(G1Affine);
 impl_debug_using_serialize_for!(G1Projective);
 
+impl G1Projective {
+    /// Multiscalar multiplication also returning the intermediate sums
+    ///
+    /// Returns `p1*s1 + p2*s2 + ... + pn*sn`, where
+    /// `n = min(points.len(), scalars.len())`, together with the prefix sums
+    /// `p1*s1`, `p1*s1 + p2*s2`, ..., the last of which equals the returned
+    /// total. The total is the same as that of `muln_vartime`.
+    ///
+    /// Returns the identity element and no prefix sums if terms is empty.
+    ///
+    /// Computing the prefix sums requires evaluating each product on its own,
+    /// so this forgoes the speedup of Pippenger's algorithm used by
+    /// `muln_vartime`, and is correspondingly slower for many terms. Only use
+    /// it if the prefix sums are actually needed.
+    pub fn sum_of_products_with_partials(points: &[Self], scalars: &[Scalar]) -> (Self, Vec<Self>) {
+        let mut partials = Vec::with_capacity(std::cmp::min(points.len(), scalars.len()));
+        let mut accum = Self::identity();
+        for (p, s) in points.iter().zip(scalars.iter()) {
+            accum += p * s;
+            partials.push(accum.clone());
+        }
+        (accum, partials)
+    }
+}
+
 define_affine_and_projective_types!(G2Affine, G2Projective, 96);
 declare_addsub_ops_for!(G2Projective);
 declare_mixed_addition_ops_for!(G2Projective, G2Affine);
+declare_from_iterator_for!(G2Projective, G2Affine);
 declare_windowed_scalar_mul_ops_for!(G2Projective, 4);
 declare_mul2_impl_for!(G2Projective, G2Mul2Table, 2, 3);
 declare_muln_vartime_dispatch_for!(
@@ -1847,6 +2647,51 @@ declare_muln_vartime_affine_impl_for!(G2Projective, G2Affine);
 impl_debug_using_serialize_for!(G2Affine);
 impl_debug_using_serialize_for!(G2Projective);
 
+/// The window size used by `ScalarMulTable`, which is the same as that used
+/// by `point * scalar` for G1 and G2
+type ScalarMulTableWindow = WindowInfo<4>;
+
+/// A scalar recoded for multiplying many points by it
+///
+/// Multiplying a point by a scalar first splits the scalar into windows.
+/// This table holds the windows of a fixed scalar, so that they are computed
+/// only once when multiplying many different points by the same scalar, as
+/// with a private key applied to a batch of points.
+///
+/// Like `point * scalar`, the multiplications run in constant time. The
+/// windows are as secret as the scalar itself, so they are zeroized on drop.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct ScalarMulTable {
+    windows: [u8; ScalarMulTableWindow::WINDOWS],
+}
+
+impl ScalarMulTable {
+    fn new(scalar: &Scalar) -> Self {
+        let s = scalar.serialize();
+        let mut windows = [0u8; ScalarMulTableWindow::WINDOWS];
+        for (i, w) in windows.iter_mut().enumerate() {
+            *w = ScalarMulTableWindow::extract(&s, i);
+        }
+        Self { windows }
+    }
+
+    /// Multiply a point in G1 by the scalar
+    ///
+    /// Equivalent to `point * scalar`
+    pub fn mul_g1(&self, point: &G1Affine) -> G1Projective {
+        G1Projective::from(point)
+            .windowed_mul_with_windows::<{ ScalarMulTableWindow::SIZE }>(|i| self.windows[i])
+    }
+
+    /// Multiply a point in G2 by the scalar
+    ///
+    /// Equivalent to `point * scalar`
+    pub fn mul_g2(&self, point: &G2Affine) -> G2Projective {
+        G2Projective::from(point)
+            .windowed_mul_with_windows::<{ ScalarMulTableWindow::SIZE }>(|i| self.windows[i])
+    }
+}
+
 /// An element of the group Gt
 #[derive(Clone, Debug, Eq, PartialEq, Zeroize, ZeroizeOnDrop)]
 pub struct Gt {
@@ -1933,6 +2778,15 @@ impl Gt {
         Self::new(ic_bls12_381::multi_miller_loop(&inners).final_exponentiation())
     }
 
+    /// Return true if the multi-pairing of `terms` is the identity element
+    ///
+    /// This is equivalent to `Gt::multipairing(terms).is_identity()`, which is
+    /// how pairing equations such as BLS signature verification are usually
+    /// checked, after moving all of the terms to one side.
+    pub fn multipairing_is_identity(terms: &[(&G1Affine, &G2Prepared)]) -> bool {
+        Self::multipairing(terms).is_identity()
+    }
+
     /// Return true if this is the identity element
     pub fn is_identity(&self) -> bool {
         bool::from(self.value.is_identity())
@@ -1949,6 +2803,11 @@ impl Gt {
         Self::new(self.value.double())
     }
 
+    /// Replace this element with its doubling
+    pub fn double_in_place(&mut self) {
+        self.value = self.value.double();
+    }
+
     /// Return some arbitrary bytes which represent this Gt element
     ///
     /// These are not deserializable, and serve only to uniquely identify
@@ -1957,6 +2816,24 @@ impl Gt {
         self.value.to_bytes()
     }
 
+    /// Return a 64-bit fingerprint of this element
+    ///
+    /// This is the first 8 bytes of SHA-256 of [`Gt::tag`]. Equal elements
+    /// always have equal fingerprints, while distinct elements have equal
+    /// fingerprints only with negligible probability, so fingerprints can be
+    /// computed once and compared to quickly rule out equality among many
+    /// elements, see [`FingerprintedGt`]. Computing a fingerprint costs more
+    /// than a single comparison using `==`, so it does not help when
+    /// comparing two elements once.
+    pub fn fingerprint(&self) -> u64 {
+        use sha2::Digest;
+
+        let digest = sha2::Sha256::digest(&self.tag());
+        let mut fbytes = [0u8; 8];
+        fbytes.copy_from_slice(&digest[..8]);
+        u64::from_be_bytes(fbytes)
+    }
+
     /// Return a hash value of this element suitable for linear search
     ///
     /// # Warning
@@ -1978,6 +2855,35 @@ impl Gt {
         extract4(&tag, 0) ^ extract4(&tag, 32)
     }
 
+    /// Multiscalar multiplication using Pippenger's algorithm
+    ///
+    /// Equivalent to p1*s1 + p2*s2 + p3*s3 + ... + pn*sn,
+    /// where `n = min(points.len(), scalars.len())`.
+    ///
+    /// Returns the identity element if terms is empty.
+    ///
+    /// Warning: this function leaks information about the scalars via
+    /// memory-based side channels. Do not use this function with secret
+    /// scalars.
+    pub fn muln_vartime(points: &[Self], scalars: &[Scalar]) -> Self {
+        if points.len() == 1 {
+            &points[0] * &scalars[0]
+        } else if points.len() < GT_USE_W3_LARGER_THAN {
+            Self::muln_vartime_naive(points, scalars)
+        } else if points.len() < GT_USE_W4_LARGER_THAN {
+            Self::muln_vartime_window_3(points, scalars)
+        } else {
+            Self::muln_vartime_window_4(points, scalars)
+        }
+    }
+
+    fn muln_vartime_naive(points: &[Self], scalars: &[Scalar]) -> Self {
+        points
+            .iter()
+            .zip(scalars.iter())
+            .fold(Self::identity(), |accum, (p, s)| accum + p * s)
+    }
+
     /// Return the result of g*val where g is the standard generator
     ///
     /// This function avoids leaking val through timing side channels,
@@ -2001,6 +2907,60 @@ impl Gt {
 
 declare_addsub_ops_for!(Gt);
 declare_windowed_scalar_mul_ops_for!(Gt, 4);
+declare_muln_vartime_impls_for!(Gt, 3, 4);
+
+/// A Gt element along with its cached fingerprint
+///
+/// Comparisons first compare the fingerprints, which rejects unequal
+/// elements without comparing the full Fp12 elements. This is useful when the
+/// same elements are compared many times, as the fingerprint of each element
+/// is only computed once.
+#[derive(Clone, Debug)]
+pub struct FingerprintedGt {
+    value: Gt,
+    fingerprint: u64,
+}
+
+impl FingerprintedGt {
+    /// Compute the fingerprint of `value`
+    pub fn new(value: Gt) -> Self {
+        let fingerprint = value.fingerprint();
+        Self { value, fingerprint }
+    }
+
+    /// Return the Gt element
+    pub fn value(&self) -> &Gt {
+        &self.value
+    }
+
+    /// Return the cached fingerprint of the element, see [`Gt::fingerprint`]
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    /// Return false if the elements are certainly not equal
+    ///
+    /// This only compares the fingerprints, so it may return true for
+    /// distinct elements, with negligible probability. Use `==` for an exact
+    /// comparison.
+    pub fn likely_eq(&self, other: &Self) -> bool {
+        self.fingerprint == other.fingerprint
+    }
+}
+
+impl From<Gt> for FingerprintedGt {
+    fn from(value: Gt) -> Self {
+        Self::new(value)
+    }
+}
+
+impl PartialEq for FingerprintedGt {
+    fn eq(&self, other: &Self) -> bool {
+        self.likely_eq(other) && self.value == other.value
+    }
+}
+
+impl Eq for FingerprintedGt {}
 
 /// An element of the group G2 prepared for the Miller loop
 #[derive(Clone, Debug)]
@@ -2014,11 +2974,30 @@ lazy_static::lazy_static! {
 }
 
 impl G2Prepared {
+    /// The number of line coefficients of a prepared point, one for each
+    /// doubling and addition step of the Miller loop: 63 doublings and 5
+    /// additions for the BLS12-381 parameter 0xd201000000010000
+    const COEFFICIENTS: usize = 68;
+
+    /// The size in bytes of a line coefficient, which is made of three
+    /// elements of Fp2, each of which is two elements of Fp
+    const COEFFICIENT_BYTES: usize = 3 * 2 * 48;
+
     /// Create a new G2Prepared from the inner type
     pub(crate) fn new(value: ic_bls12_381::G2Prepared) -> Self {
         Self { value }
     }
 
+    /// Return the number of heap bytes used by this prepared point
+    ///
+    /// This is the size of the storage of the precomputed line coefficients,
+    /// which does not depend on the point that was prepared; the same number
+    /// of coefficients is computed even for the identity. It excludes the
+    /// inline size of the struct itself.
+    pub fn heap_size(&self) -> usize {
+        Self::COEFFICIENTS * Self::COEFFICIENT_BYTES
+    }
+
     pub(crate) fn inner(&self) -> &ic_bls12_381::G2Prepared {
         &self.value
     }
@@ -2032,6 +3011,24 @@ impl G2Prepared {
     pub fn neg_generator() -> &'static Self {
         &G2PREPARED_NEG_G
     }
+
+    /// Prepare a batch of points
+    ///
+    /// Equivalent to calling `G2Prepared::from` on each point. Affine points
+    /// need no normalization, and the line coefficients are specific to each
+    /// point, so there is no work shared between the inputs; see
+    /// batch_from_projective for the case where there is.
+    pub fn batch_from(points: &[G2Affine]) -> Vec<Self> {
+        points.iter().map(Self::from).collect()
+    }
+
+    /// Prepare a batch of points in projective form
+    ///
+    /// The conversion to affine form is performed for all points at
+    /// once, requiring a single field inversion instead of one per point.
+    pub fn batch_from_projective(points: &[G2Projective]) -> Vec<Self> {
+        Self::batch_from(&G2Projective::batch_normalize(points))
+    }
 }
 
 impl From<&G2Affine> for G2Prepared {
@@ -2058,6 +3055,30 @@ impl From<G2Projective> for G2Prepared {
     }
 }
 
+/// Pairing with a fixed element of G2
+///
+/// The G2 argument is prepared once when this is constructed, so that each
+/// call to [`PairingWithFixedG2::pair`] only needs a Miller loop using the
+/// precomputed line coefficients followed by the final exponentiation.
+#[derive(Clone, Debug)]
+pub struct PairingWithFixedG2 {
+    g2: G2Prepared,
+}
+
+impl PairingWithFixedG2 {
+    /// Prepare the fixed G2 argument of the pairing
+    pub fn new(g2: &G2Affine) -> Self {
+        Self { g2: g2.into() }
+    }
+
+    /// Compute e(g1, g2) where g2 is the fixed argument
+    ///
+    /// This is equal to `Gt::pairing(g1, g2)`
+    pub fn pair(&self, g1: &G1Affine) -> Gt {
+        Gt::multipairing(&[(g1, &self.g2)])
+    }
+}
+
 /// Perform BLS signature verification
 ///
 /// The naive version of this function requires two pairings, but it
@@ -2072,7 +3093,7 @@ pub fn verify_bls_signature(
 
     let g2_gen = G2Prepared::neg_generator();
     let pub_key_prepared = G2Prepared::from(public_key);
-    Gt::multipairing(&[(signature, g2_gen), (message, &pub_key_prepared)]).is_identity()
+    Gt::multipairing_is_identity(&[(signature, g2_gen), (message, &pub_key_prepared)])
 }
 
 /// Number of random bits in a [`Scalar`] that is used for batched signature
@@ -2166,10 +3187,17 @@ pub fn verify_bls_signature_batch<R: RngCore + CryptoRng>(
 /// For details, see Section 5.1 in "Batch Verification of Short Signatures"
 /// by J. Camenisch, S. Hohenberger, M. Ø. Pedersen. In Eurocrypt'07.
 /// https://eprint.iacr.org/2007/172.pdf.
+///
+/// A batch consisting of a single signature is verified directly using
+/// [`verify_bls_signature`], as randomization gains nothing in that case.
 pub fn verify_bls_signature_batch_distinct<R: RngCore + CryptoRng>(
     sigs_pks_msgs: &[(&G1Affine, &G2Affine, &G1Affine)],
     rng: &mut R,
 ) -> bool {
+    if let [(sig, pk, msg)] = sigs_pks_msgs {
+        return verify_bls_signature(sig, pk, msg);
+    }
+
     let random_scalars =
         Scalar::batch_sparse_random(rng, sigs_pks_msgs.len(), NUM_BITS_BATCH_VERIFICATION);
     let (sigs, pks, msgs): (Vec<_>, Vec<_>, Vec<_>) = multiunzip(sigs_pks_msgs.to_vec());
@@ -2192,7 +3220,51 @@ pub fn verify_bls_signature_batch_distinct<R: RngCore + CryptoRng>(
         multipairing_inputs.push((msg, pk));
     }
 
-    Gt::multipairing(&multipairing_inputs[..]).is_identity()
+    Gt::multipairing_is_identity(&multipairing_inputs[..])
+}
+
+/// Performs the same verification as [`verify_bls_signature_batch_distinct`]
+/// using up to `num_threads` threads.
+///
+/// The batch is split into `num_threads` partitions of nearly equal size,
+/// each of which is verified on its own thread using a fresh RNG returned by
+/// `rng_factory`. The batch is accepted only if every partition is accepted.
+///
+/// If `num_threads` is at most 1 or the batch has at most one element, the
+/// batch is verified on the calling thread. No more threads than elements of
+/// the batch are started.
+pub fn verify_bls_signature_batch_distinct_parallel<R, F>(
+    sigs_pks_msgs: &[(&G1Affine, &G2Affine, &G1Affine)],
+    num_threads: usize,
+    rng_factory: F,
+) -> bool
+where
+    R: RngCore + CryptoRng,
+    F: Fn() -> R + Sync,
+{
+    let num_threads = num_threads.min(sigs_pks_msgs.len());
+    if num_threads <= 1 {
+        return verify_bls_signature_batch_distinct(sigs_pks_msgs, &mut rng_factory());
+    }
+
+    let len = sigs_pks_msgs.len();
+    std::thread::scope(|scope| {
+        let threads: Vec<_> = (0..num_threads)
+            .map(|i| {
+                let partition = &sigs_pks_msgs[i * len / num_threads..(i + 1) * len / num_threads];
+                let rng_factory = &rng_factory;
+                scope.spawn(move || {
+                    verify_bls_signature_batch_distinct(partition, &mut rng_factory())
+                })
+            })
+            .collect();
+
+        // Join all threads even if a partition was already rejected
+        threads
+            .into_iter()
+            .map(|t| t.join().expect("batch verification thread panicked"))
+            .fold(true, |accum, valid| accum & valid)
+    })
 }
 
 /// Performs the verification of a batch of BLS signatures that is faster than
@@ -2204,11 +3276,18 @@ pub fn verify_bls_signature_batch_distinct<R: RngCore + CryptoRng>(
 /// For details, see Section 5.2 in "Short Signatures from the Weil Pairing"
 /// by Dan Boneh, Ben Lynn, and Hovav Shacham. In Jounal of Cryptology'04.
 /// https://link.springer.com/content/pdf/10.1007/s00145-004-0314-9.pdf.
+///
+/// Single-signature batches skip the randomization and are checked with
+/// [`verify_bls_signature`].
 pub fn verify_bls_signature_batch_same_pk<R: RngCore + CryptoRng>(
     sigs_msgs: &[(&G1Affine, &G1Affine)],
     public_key: &G2Affine,
     rng: &mut R,
 ) -> bool {
+    if let [(sig, msg)] = sigs_msgs {
+        return verify_bls_signature(sig, public_key, msg);
+    }
+
     let random_scalars =
         Scalar::batch_sparse_random(rng, sigs_msgs.len(), NUM_BITS_BATCH_VERIFICATION);
     let (sigs, msgs): (Vec<_>, Vec<_>) = sigs_msgs.iter().copied().unzip();
@@ -2220,11 +3299,10 @@ pub fn verify_bls_signature_batch_same_pk<R: RngCore + CryptoRng>(
     let aggregate_message = G1Projective::muln_affine_sparse_vartime(&msgs_scalars[..]).to_affine();
     let prepared_pk = G2Prepared::from(public_key);
 
-    Gt::multipairing(&[
+    Gt::multipairing_is_identity(&[
         (&aggregate_sig, inv_g2_gen),
         (&aggregate_message, &prepared_pk),
     ])
-    .is_identity()
 }
 
 /// Performs a verification of a batch of BLS signatures that is faster than
@@ -2236,11 +3314,18 @@ pub fn verify_bls_signature_batch_same_pk<R: RngCore + CryptoRng>(
 /// For details, see Section 5.2 in "Short Signatures from the Weil Pairing"
 /// by Dan Boneh, Ben Lynn, and Hovav Shacham. In Jounal of Cryptology'04.
 /// https://link.springer.com/content/pdf/10.1007/s00145-004-0314-9.pdf.
+///
+/// If `sigs_pks` holds just one signature, this is equivalent to (and
+/// implemented as) [`verify_bls_signature`].
 pub fn verify_bls_signature_batch_same_msg<R: RngCore + CryptoRng>(
     sigs_pks: &[(&G1Affine, &G2Affine)],
     message: &G1Affine,
     rng: &mut R,
 ) -> bool {
+    if let [(sig, pk)] = sigs_pks {
+        return verify_bls_signature(sig, pk, message);
+    }
+
     let random_scalars =
         Scalar::batch_sparse_random(rng, sigs_pks.len(), NUM_BITS_BATCH_VERIFICATION);
     let (sigs, pks): (Vec<_>, Vec<_>) = sigs_pks.iter().copied().unzip();
@@ -2252,7 +3337,35 @@ pub fn verify_bls_signature_batch_same_msg<R: RngCore + CryptoRng>(
     let aggregate_pk = G2Projective::muln_affine_sparse_vartime(&pks_scalars[..]);
     let pub_key_prepared = G2Prepared::from(aggregate_pk);
 
-    Gt::multipairing(&[(&aggregate_sig, inv_g2_gen), (message, &pub_key_prepared)]).is_identity()
+    Gt::multipairing_is_identity(&[(&aggregate_sig, inv_g2_gen), (message, &pub_key_prepared)])
+}
+
+/// Performs the same verification as [`verify_bls_signature_batch_same_msg`]
+/// and, if the batch is valid, returns the aggregate of the public keys
+///
+/// The aggregate is the sum of all public keys of the batch, as computed by
+/// [`aggregate_public_keys`]. It is only computed if the batch is valid.
+pub fn verify_bls_signature_batch_same_msg_agg<R: RngCore + CryptoRng>(
+    sigs_pks: &[(&G1Affine, &G2Affine)],
+    message: &G1Affine,
+    rng: &mut R,
+) -> Result<G2Affine, BatchError> {
+    if !verify_bls_signature_batch_same_msg(sigs_pks, message, rng) {
+        return Err(BatchError::InvalidBatch);
+    }
+    let pks: Vec<_> = sigs_pks.iter().map(|(_sig, pk)| *pk).collect();
+    Ok(aggregate_public_keys(&pks))
+}
+
+/// Returns the sum of the given public keys
+///
+/// Returns the identity element if `public_keys` is empty.
+pub fn aggregate_public_keys(public_keys: &[&G2Affine]) -> G2Affine {
+    let mut aggregate = G2Projective::identity();
+    for pk in public_keys {
+        aggregate += *pk;
+    }
+    aggregate.to_affine()
 }
 
 struct WindowInfo<const WINDOW_SIZE: usize> {}
@@ -136,3 +136,72 @@ fn random_sparse_scalars_have_correct_hamming_weight() {
         }
     }
 }
+
+#[test]
+fn scalar_multiplicative_generator_matches_field_implementation() {
+    use pairing::group::ff::PrimeField;
+    assert_eq!(
+        *crate::Scalar::MULTIPLICATIVE_GENERATOR.inner(),
+        ic_bls12_381::Scalar::multiplicative_generator()
+    );
+}
+
+#[cfg(feature = "proptest")]
+mod proptests {
+    use crate::{G1Affine, G2Affine, Gt, Scalar};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn scalar_serialization_roundtrips(s in any::<Scalar>()) {
+            prop_assert_eq!(Scalar::deserialize(&s.serialize()).ok(), Some(s));
+        }
+
+        #[test]
+        fn g1_serialization_roundtrips(pt in any::<G1Affine>()) {
+            prop_assert!(pt.is_torsion_free());
+            prop_assert_eq!(G1Affine::deserialize(&pt.serialize()).ok(), Some(pt));
+        }
+
+        #[test]
+        fn g2_serialization_roundtrips(pt in any::<G2Affine>()) {
+            prop_assert!(pt.is_torsion_free());
+            prop_assert_eq!(G2Affine::deserialize(&pt.serialize()).ok(), Some(pt));
+        }
+
+        #[test]
+        fn gt_negation_roundtrips(gt in any::<Gt>()) {
+            prop_assert_eq!(gt.neg().neg(), gt.clone());
+            prop_assert!((&gt + &gt.neg()).is_identity());
+        }
+    }
+}
+
+#[test]
+fn random_not_in_subgroup_is_on_curve_but_not_torsion_free() {
+    use crate::{G1Affine, G2Affine};
+
+    let rng = &mut reproducible_rng();
+
+    for _ in 0..10 {
+        let pt = G1Affine::random_not_in_subgroup(rng);
+        assert!(bool::from(pt.inner().is_on_curve()));
+        assert!(!pt.is_torsion_free());
+        assert!(!pt.is_identity());
+        assert!(G1Affine::deserialize(&pt.serialize()).is_err());
+        assert_eq!(
+            G1Affine::deserialize_unchecked(&pt.serialize()).unwrap(),
+            pt
+        );
+
+        let pt = G2Affine::random_not_in_subgroup(rng);
+        assert!(bool::from(pt.inner().is_on_curve()));
+        assert!(!pt.is_torsion_free());
+        assert!(!pt.is_identity());
+        assert!(G2Affine::deserialize(&pt.serialize()).is_err());
+        assert_eq!(
+            G2Affine::deserialize_unchecked(&pt.serialize()).unwrap(),
+            pt
+        );
+    }
+}
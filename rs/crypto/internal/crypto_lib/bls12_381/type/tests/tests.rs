@@ -228,6 +228,97 @@ fn test_scalar_from_integer_type() {
     }
 }
 
+#[test]
+fn test_scalar_hadamard() {
+    let mut rng = reproducible_rng();
+
+    for n in [0, 1, 2, 10] {
+        let a: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+        let b: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+
+        let expected: Vec<Scalar> = a.iter().zip(b.iter()).map(|(x, y)| x * y).collect();
+        assert_eq!(Scalar::hadamard(&a, &b), Ok(expected.clone()));
+
+        let mut product = a.clone();
+        assert_eq!(Scalar::hadamard_assign(&mut product, &b), Ok(()));
+        assert_eq!(product, expected);
+
+        let ones = vec![Scalar::one(); n];
+        assert_eq!(Scalar::hadamard(&a, &ones), Ok(a.clone()));
+        assert_eq!(Scalar::hadamard(&ones, &b), Ok(b.clone()));
+
+        let mut longer = b.clone();
+        longer.push(Scalar::random(&mut rng));
+        let mismatch = LengthMismatch { lhs: n, rhs: n + 1 };
+        assert_eq!(Scalar::hadamard(&a, &longer), Err(mismatch));
+        let mut unchanged = a.clone();
+        assert_eq!(
+            Scalar::hadamard_assign(&mut unchanged, &longer),
+            Err(mismatch)
+        );
+        assert_eq!(unchanged, a);
+    }
+}
+
+#[test]
+fn test_scalar_division() {
+    let mut rng = reproducible_rng();
+
+    for _ in 0..30 {
+        let a = Scalar::random(&mut rng);
+        let b = Scalar::random(&mut rng);
+
+        let q = a.checked_div(&b).expect("random scalar should not be zero");
+        assert_eq!(&q * &b, a);
+        assert_eq!(&a / &b, q);
+        assert_eq!(a.clone() / b.clone(), q);
+        assert_eq!(&a * b.inverse().unwrap(), q);
+        assert_eq!(a.checked_div(&a), Some(Scalar::one()));
+
+        assert_eq!(a.checked_div(&Scalar::zero()), None);
+        assert_eq!(Scalar::zero().checked_div(&a), Some(Scalar::zero()));
+    }
+
+    assert_eq!(
+        Scalar::from_u64(6) / Scalar::from_u64(3),
+        Scalar::from_u64(2)
+    );
+}
+
+#[test]
+#[should_panic(expected = "Division of a scalar by zero")]
+fn test_scalar_division_by_zero_panics() {
+    let _ = Scalar::one() / Scalar::zero();
+}
+
+#[test]
+fn test_scalar_from_i64() {
+    let mut rng = reproducible_rng();
+
+    assert_eq!(Scalar::from_i64(0), Scalar::zero());
+    assert_eq!(Scalar::from_i64(1), Scalar::one());
+    assert_eq!(Scalar::from_i64(-1), Scalar::one().neg());
+
+    // check overflow handling (i64::MIN.abs() is greater than i64::MAX)
+    assert_eq!(
+        Scalar::from_i64(i64::MIN).neg(),
+        Scalar::from_i64(i64::MAX) + Scalar::one()
+    );
+    assert_eq!(Scalar::from_i64(i64::MIN), Scalar::from_u64(1 << 63).neg());
+
+    for _ in 0..30 {
+        let a = rng.gen::<i32>() as i64;
+        let b = rng.gen::<i32>() as i64;
+
+        assert_eq!(
+            Scalar::from_i64(a) + Scalar::from_i64(b),
+            Scalar::from_i64(a + b)
+        );
+        assert_eq!(Scalar::from_i64(-a), Scalar::from_i64(a).neg());
+        assert_eq!(Scalar::from_i64(a), Scalar::from_i32(a as i32));
+    }
+}
+
 #[test]
 fn test_scalar_small_random() {
     let mut rng = reproducible_rng();
@@ -296,6 +387,304 @@ fn test_scalar_addition() {
     }
 }
 
+#[test]
+fn test_scalar_padded_bytes() {
+    let mut rng = reproducible_rng();
+
+    for _ in 0..30 {
+        let s = Scalar::random(&mut rng);
+
+        let b32 = s.to_padded_bytes(32).unwrap();
+        assert_eq!(b32, s.serialize().to_vec());
+        assert_eq!(Scalar::from_padded_bytes(&b32).unwrap(), s);
+
+        let b48 = s.to_padded_bytes(48).unwrap();
+        assert_eq!(b48.len(), 48);
+        assert_eq!(&b48[..16], &[0u8; 16]);
+        assert_eq!(&b48[16..], &s.serialize());
+        assert_eq!(Scalar::from_padded_bytes(&b48).unwrap(), s);
+
+        // A random scalar almost certainly needs all 32 bytes
+        if s.serialize()[0] != 0 {
+            assert!(s.to_padded_bytes(31).is_none());
+        }
+
+        // Non-zero padding is rejected
+        let mut bad = b48.clone();
+        bad[0] = 1;
+        assert_eq!(
+            Scalar::from_padded_bytes(&bad),
+            Err(ScalarDeserError::OutOfRange)
+        );
+    }
+
+    // Small values fit into small widths
+    let s = Scalar::from_u64(0x1234);
+    assert_eq!(s.to_padded_bytes(2).unwrap(), vec![0x12, 0x34]);
+    assert!(s.to_padded_bytes(1).is_none());
+    assert_eq!(Scalar::from_padded_bytes(&[0x12, 0x34]).unwrap(), s);
+    assert_eq!(Scalar::zero().to_padded_bytes(0).unwrap(), Vec::<u8>::new());
+    assert_eq!(Scalar::from_padded_bytes(&[]).unwrap(), Scalar::zero());
+
+    // Values which are not reduced are rejected at any width
+    let mut modulus = vec![0u8; 16];
+    modulus.extend_from_slice(&Scalar::MODULUS);
+    assert_eq!(
+        Scalar::from_padded_bytes(&modulus),
+        Err(ScalarDeserError::OutOfRange)
+    );
+}
+
+#[test]
+fn test_scalar_from_seed() {
+    let s = Scalar::from_seed(b"test-vector", 0);
+    assert_eq!(s, Scalar::from_seed(b"test-vector", 0));
+    assert!(!s.is_zero());
+
+    let scalars: Vec<_> = (0..100)
+        .map(|i| Scalar::from_seed(b"test-vector", i))
+        .collect();
+    for i in 0..scalars.len() {
+        for j in 0..i {
+            assert_ne!(scalars[i], scalars[j]);
+        }
+    }
+
+    assert_ne!(s, Scalar::from_seed(b"other-vector", 0));
+    // The domain is length prefixed, so it cannot run into the index
+    assert_ne!(
+        Scalar::from_seed(b"ab", 0),
+        Scalar::from_seed(b"a", u32::from_be_bytes([b'b', 0, 0, 0]))
+    );
+
+    // Derived values must not change, as tests may depend on them
+    assert_eq!(
+        hex::encode(Scalar::from_seed(b"test-vector", 1).serialize()),
+        "429a40fd143d4524130cab135278a6e055cdc1b3bc0016e043eac2c9a667f094"
+    );
+}
+
+#[test]
+fn test_scalar_is_high_and_canonical_signed() {
+    let mut rng = reproducible_rng();
+
+    let minus_one = Scalar::one().neg();
+    // (order-1)/2 is the largest low scalar
+    let largest_low = &minus_one * &Scalar::from_u64(2).inverse().unwrap();
+    let smallest_high = &largest_low + &Scalar::one();
+
+    assert!(!Scalar::zero().is_high());
+    assert!(!Scalar::one().is_high());
+    assert!(minus_one.is_high());
+    assert!(!largest_low.is_high());
+    assert!(smallest_high.is_high());
+
+    assert_eq!(Scalar::zero().canonical_signed(), (Scalar::zero(), false));
+    assert_eq!(minus_one.canonical_signed(), (Scalar::one(), true));
+    assert_eq!(largest_low.canonical_signed(), (largest_low.clone(), false));
+    assert_eq!(smallest_high.canonical_signed(), (largest_low, true));
+
+    for _ in 0..100 {
+        let s = Scalar::random(&mut rng);
+        assert_ne!(s.is_high(), s.neg().is_high());
+
+        let (c, negated) = s.canonical_signed();
+        assert!(!c.is_high());
+        assert_eq!(negated, s.is_high());
+        assert_eq!(c, if negated { s.neg() } else { s.clone() });
+    }
+}
+
+#[test]
+fn test_scalar_conditional_select_and_assign() {
+    use subtle::Choice;
+
+    let mut rng = reproducible_rng();
+
+    for (a, b) in [
+        (Scalar::zero(), Scalar::random(&mut rng)),
+        (Scalar::random(&mut rng), Scalar::zero()),
+        (Scalar::random(&mut rng), Scalar::random(&mut rng)),
+    ] {
+        assert_eq!(Scalar::conditional_select(&a, &b, Choice::from(0)), a);
+        assert_eq!(Scalar::conditional_select(&a, &b, Choice::from(1)), b);
+
+        let mut x = a.clone();
+        x.conditional_assign(&b, Choice::from(0));
+        assert_eq!(x, a);
+        x.conditional_assign(&b, Choice::from(1));
+        assert_eq!(x, b);
+    }
+}
+
+#[test]
+fn test_scalar_sqrt() {
+    let mut rng = reproducible_rng();
+
+    assert_eq!(Scalar::zero().sqrt().unwrap(), Scalar::zero());
+    let one_root = Scalar::one().sqrt().unwrap();
+    assert!(one_root == Scalar::one() || one_root == Scalar::one().neg());
+
+    // The multiplicative generator is a quadratic non-residue
+    let non_residue = Scalar::MULTIPLICATIVE_GENERATOR;
+    assert!(bool::from(non_residue.sqrt().is_none()));
+
+    for _ in 0..30 {
+        let r = Scalar::random(&mut rng);
+        let square = &r * &r;
+
+        let root = square.sqrt().unwrap();
+        assert_eq!(&root * &root, square);
+        assert!(root == r || root == r.neg());
+
+        // A non-residue times a non-zero square is again a non-residue
+        if !r.is_zero() {
+            assert!(bool::from((&square * &non_residue).sqrt().is_none()));
+        }
+    }
+}
+
+#[test]
+fn test_scalar_sub_is_zero() {
+    let mut rng = reproducible_rng();
+
+    assert!(Scalar::zero().sub_is_zero(&Scalar::zero()));
+    assert!(!Scalar::one().sub_is_zero(&Scalar::zero()));
+    assert!(!Scalar::zero().sub_is_zero(&Scalar::one()));
+
+    let xs = (1..=100).map(Scalar::from_u64).collect::<Vec<_>>();
+    for (i, xi) in xs.iter().enumerate() {
+        for (j, xj) in xs.iter().enumerate() {
+            assert_eq!(xi.sub_is_zero(xj), i == j);
+        }
+    }
+
+    for _ in 0..30 {
+        let a = Scalar::random(&mut rng);
+        let b = Scalar::random(&mut rng);
+        assert!(a.sub_is_zero(&a));
+        assert_eq!(a.sub_is_zero(&b), a == b);
+        assert_eq!(a.sub_is_zero(&b), (&a - &b).is_zero());
+        assert!(!a.sub_is_zero(&(&a + &Scalar::one())));
+    }
+}
+
+#[test]
+fn test_scalar_sum_and_product() {
+    let mut rng = reproducible_rng();
+
+    let empty: Vec<Scalar> = vec![];
+    assert_eq!(empty.iter().sum::<Scalar>(), Scalar::zero());
+    assert_eq!(empty.iter().product::<Scalar>(), Scalar::one());
+    assert_eq!(empty.clone().into_iter().sum::<Scalar>(), Scalar::zero());
+    assert_eq!(empty.into_iter().product::<Scalar>(), Scalar::one());
+
+    let small: Vec<Scalar> = (1..=5).map(Scalar::from_u64).collect();
+    assert_eq!(small.iter().sum::<Scalar>(), Scalar::from_u64(15));
+    assert_eq!(small.iter().product::<Scalar>(), Scalar::from_u64(120));
+
+    for len in [1, 2, 10, 31] {
+        let scalars = Scalar::batch_random(&mut rng, len);
+
+        let mut expected_sum = Scalar::zero();
+        let mut expected_product = Scalar::one();
+        for s in &scalars {
+            expected_sum += s;
+            expected_product *= s;
+        }
+
+        assert_eq!(scalars.iter().sum::<Scalar>(), expected_sum);
+        assert_eq!(scalars.iter().product::<Scalar>(), expected_product);
+        assert_eq!(scalars.clone().into_iter().sum::<Scalar>(), expected_sum);
+        assert_eq!(scalars.into_iter().product::<Scalar>(), expected_product);
+    }
+}
+
+#[test]
+fn test_scalar_hex_formatting() {
+    let rng = &mut reproducible_rng();
+
+    let scalars = [
+        Scalar::zero(),
+        Scalar::one(),
+        Scalar::one().neg(),
+        Scalar::random(rng),
+    ];
+
+    for s in &scalars {
+        let hex = hex::encode(s.serialize());
+        assert_eq!(format!("{:x}", s), hex);
+        assert_eq!(format!("{:X}", s), hex.to_uppercase());
+        assert_eq!(format!("{:#x}", s), format!("0x{}", hex));
+
+        let display = format!("{}", s);
+        let prefix = display
+            .strip_suffix("...")
+            .expect("missing truncation marker");
+        assert_eq!(prefix.len(), 2 * Scalar::DISPLAY_PREFIX_BYTES);
+        assert!(hex.starts_with(prefix));
+    }
+
+    assert_eq!(format!("{}", Scalar::one()), "0000000000000000...");
+}
+
+#[test]
+fn test_scalar_u64_digits() {
+    let mut rng = reproducible_rng();
+
+    assert_eq!(Scalar::zero().to_u64_digits(), [0, 0, 0, 0]);
+    assert_eq!(Scalar::one().to_u64_digits(), [1, 0, 0, 0]);
+    for v in [2, 0xdeadbeef, u64::MAX] {
+        assert_eq!(Scalar::from_u64(v).to_u64_digits(), [v, 0, 0, 0]);
+        assert_eq!(
+            Scalar::from_u64_digits([v, 0, 0, 0]),
+            Ok(Scalar::from_u64(v))
+        );
+    }
+    let two_to_64 = Scalar::from_u64(u64::MAX) + Scalar::one();
+    assert_eq!(two_to_64.to_u64_digits(), [0, 1, 0, 0]);
+
+    // The digits are the big-endian serialization read as little-endian limbs
+    let order_minus_one = Scalar::one().neg();
+    assert_eq!(
+        order_minus_one.to_u64_digits(),
+        [
+            0xffffffff00000000,
+            0x53bda402fffe5bfe,
+            0x3339d80809a1d805,
+            0x73eda753299d7d48
+        ]
+    );
+
+    for _ in 0..100 {
+        let s = Scalar::random(&mut rng);
+        let digits = s.to_u64_digits();
+        assert_eq!(Scalar::from_u64_digits(digits), Ok(s.clone()));
+
+        let mut bytes = [0u8; Scalar::BYTES];
+        for (i, digit) in digits.iter().enumerate() {
+            bytes[Scalar::BYTES - 8 * (i + 1)..Scalar::BYTES - 8 * i]
+                .copy_from_slice(&digit.to_be_bytes());
+        }
+        assert_eq!(bytes, s.serialize());
+    }
+
+    let modulus = [
+        0xffffffff00000001,
+        0x53bda402fffe5bfe,
+        0x3339d80809a1d805,
+        0x73eda753299d7d48,
+    ];
+    assert_eq!(
+        Scalar::from_u64_digits(modulus),
+        Err(ScalarDeserError::OutOfRange)
+    );
+    assert_eq!(
+        Scalar::from_u64_digits([u64::MAX; 4]),
+        Err(ScalarDeserError::OutOfRange)
+    );
+}
+
 #[test]
 fn test_scalar_neg() {
     let mut rng = reproducible_rng();
@@ -307,6 +696,34 @@ fn test_scalar_neg() {
     }
 }
 
+#[test]
+fn test_scalar_negate_slice_and_to_vec() {
+    let mut rng = reproducible_rng();
+
+    assert_eq!(-Scalar::zero(), Scalar::zero());
+    assert_eq!(-&Scalar::zero(), Scalar::zero());
+    assert!(Scalar::negate_to_vec(&[]).is_empty());
+
+    for len in [1, 2, 17] {
+        let mut scalars = Scalar::batch_random(&mut rng, len);
+        scalars[0] = Scalar::zero();
+        let original = scalars.clone();
+
+        let negated = Scalar::negate_to_vec(&scalars);
+        assert_eq!(negated.len(), len);
+        for (n, s) in negated.iter().zip(&scalars) {
+            assert_eq!(*n, -s);
+            assert_eq!(*n, -s.clone());
+        }
+        assert_eq!(negated[0], Scalar::zero());
+
+        Scalar::negate_slice(&mut scalars);
+        assert_eq!(scalars, negated);
+        Scalar::negate_slice(&mut scalars);
+        assert_eq!(scalars, original);
+    }
+}
+
 #[test]
 fn test_scalar_inverse() {
     let mut rng = reproducible_rng();
@@ -358,6 +775,90 @@ fn test_gt_hash_has_no_collisions_in_range() {
     }
 }
 
+#[test]
+fn test_gt_fingerprint() {
+    let mut rng = reproducible_rng();
+
+    let mut fingerprints = std::collections::HashSet::new();
+
+    for _ in 0..10 {
+        let a = Scalar::random(&mut rng);
+        let b = Scalar::random(&mut rng);
+
+        // e(g1*a, g2*b) == gt*(a*b), computed in two different ways
+        let x = Gt::pairing(
+            &(G1Affine::generator() * &a).to_affine(),
+            &(G2Affine::generator() * &b).to_affine(),
+        );
+        let y = Gt::generator() * &(&a * &b);
+
+        assert_eq!(x.fingerprint(), y.fingerprint());
+        assert_eq!(x, y);
+
+        assert!(fingerprints.insert(x.fingerprint()));
+        assert_ne!(x.fingerprint(), x.neg().fingerprint());
+    }
+
+    assert_eq!(Gt::identity().fingerprint(), Gt::identity().fingerprint());
+    assert_ne!(Gt::identity().fingerprint(), Gt::generator().fingerprint());
+}
+
+#[test]
+fn test_fingerprinted_gt_equality() {
+    let mut rng = reproducible_rng();
+
+    for _ in 0..10 {
+        let a = Scalar::random(&mut rng);
+        let b = Scalar::random(&mut rng);
+
+        let x = FingerprintedGt::new(Gt::pairing(
+            &(G1Affine::generator() * &a).to_affine(),
+            &(G2Affine::generator() * &b).to_affine(),
+        ));
+        let y = FingerprintedGt::from(Gt::generator() * &(&a * &b));
+        let z = FingerprintedGt::new(x.value().neg());
+
+        assert_eq!(x.fingerprint(), x.value().fingerprint());
+
+        // Equal elements pass the fast path and are equal
+        assert!(x.likely_eq(&y));
+        assert_eq!(x, y);
+        assert_eq!(x.value(), y.value());
+
+        // Unequal elements are rejected by the fast path
+        assert!(!x.likely_eq(&z));
+        assert_ne!(x, z);
+        assert_ne!(x.value(), z.value());
+    }
+}
+
+#[test]
+fn test_gt_double_in_place_and_reference_ops() {
+    let mut rng = reproducible_rng();
+
+    let mut identity = Gt::identity();
+    identity.double_in_place();
+    assert_eq!(identity, Gt::identity());
+
+    for _ in 0..10 {
+        let a = Gt::generator() * Scalar::random(&mut rng);
+        let b = Gt::generator() * Scalar::random(&mut rng);
+        let s = Scalar::random(&mut rng);
+
+        let mut doubled = a.clone();
+        doubled.double_in_place();
+        assert_eq!(doubled, a.double());
+        assert_eq!(doubled, &a + &a);
+
+        assert_eq!(&a + &b, a.clone() + b.clone());
+        assert_eq!(&a * &s, a.clone() * s.clone());
+
+        let mut acc = a.clone();
+        acc += &b;
+        assert_eq!(acc, a.clone() + b.clone());
+    }
+}
+
 #[test]
 fn test_gt_mul_u16_is_correct() {
     let mut rng = reproducible_rng();
@@ -523,6 +1024,132 @@ fn test_multipairing() {
     }
 }
 
+#[test]
+fn test_multipairing_is_identity() {
+    let g1 = G1Affine::generator();
+    let g1n = G1Affine::generator().neg();
+    let g2p = G2Prepared::generator();
+    let g2pn = G2Prepared::neg_generator();
+
+    assert!(Gt::multipairing_is_identity(&[]));
+    assert!(!Gt::multipairing_is_identity(&[(g1, g2p)]));
+    assert!(Gt::multipairing_is_identity(&[(g1, g2p), (g1, g2pn)]));
+    assert!(Gt::multipairing_is_identity(&[(g1, g2p), (&g1n, g2p)]));
+
+    let mut rng = reproducible_rng();
+
+    for _ in 0..5 {
+        // A BLS signature check e(sig, g2) == e(H(m), pk), rearranged as
+        // e(sig, -g2) * e(H(m), pk) == 1
+        let sk = Scalar::random(&mut rng);
+        let pk = G2Prepared::from(G2Affine::from(G2Affine::generator() * &sk));
+        let message = G1Affine::hash(b"bls_signature_dst", &rng.gen::<[u8; 32]>());
+        let signature = G1Affine::from(&message * &sk);
+
+        let terms = [(&signature, g2pn), (&message, &pk)];
+        assert_eq!(
+            Gt::multipairing_is_identity(&terms),
+            Gt::multipairing(&terms) == Gt::identity()
+        );
+        assert!(Gt::multipairing_is_identity(&terms));
+
+        let tampered_signature =
+            (G1Projective::from(&signature) + G1Affine::generator()).to_affine();
+        let terms = [(&tampered_signature, g2pn), (&message, &pk)];
+        assert_eq!(
+            Gt::multipairing_is_identity(&terms),
+            Gt::multipairing(&terms) == Gt::identity()
+        );
+        assert!(!Gt::multipairing_is_identity(&terms));
+    }
+}
+
+#[test]
+fn test_pairing_with_fixed_g2() {
+    let mut rng = reproducible_rng();
+
+    let fixed_g2s = [
+        G2Affine::generator().clone(),
+        G2Affine::identity(),
+        G2Projective::random(&mut rng).to_affine(),
+    ];
+
+    for g2 in &fixed_g2s {
+        let pairing = PairingWithFixedG2::new(g2);
+
+        let mut g1s = vec![G1Affine::generator().clone(), G1Affine::identity()];
+        for _ in 0..5 {
+            g1s.push(G1Projective::random(&mut rng).to_affine());
+        }
+
+        for g1 in &g1s {
+            assert_eq!(pairing.pair(g1), Gt::pairing(g1, g2));
+        }
+    }
+}
+
+#[test]
+fn test_g2_prepared_heap_size() {
+    let mut rng = reproducible_rng();
+
+    let size = G2Prepared::generator().heap_size();
+    assert!(size > 0);
+    assert_eq!(size, 68 * 3 * 2 * 48);
+    assert_eq!(G2Prepared::neg_generator().heap_size(), size);
+    assert_eq!(G2Prepared::from(G2Affine::identity()).heap_size(), size);
+
+    for _ in 0..10 {
+        let pt = G2Affine::from(G2Projective::random(&mut rng));
+        assert_eq!(G2Prepared::from(&pt).heap_size(), size);
+    }
+}
+
+#[test]
+fn test_g2_prepared_batch() {
+    let mut rng = reproducible_rng();
+
+    assert!(G2Prepared::batch_from(&[]).is_empty());
+    assert!(G2Prepared::batch_from_projective(&[]).is_empty());
+
+    let g1 = G1Affine::generator();
+
+    for n in [1, 2, 5, 10] {
+        let mut points = Vec::with_capacity(n);
+        for i in 0..n {
+            if i == 1 {
+                points.push(G2Projective::identity());
+            } else {
+                points.push(G2Projective::random(&mut rng));
+            }
+        }
+        let affine = G2Projective::batch_normalize(&points);
+
+        let individual = affine.iter().map(G2Prepared::from).collect::<Vec<_>>();
+        let batched = G2Prepared::batch_from(&affine);
+        let batched_projective = G2Prepared::batch_from_projective(&points);
+
+        assert_eq!(batched.len(), n);
+        assert_eq!(batched_projective.len(), n);
+
+        for i in 0..n {
+            let expected = Gt::pairing(g1, &affine[i]);
+            assert_eq!(Gt::multipairing(&[(g1, &individual[i])]), expected);
+            assert_eq!(Gt::multipairing(&[(g1, &batched[i])]), expected);
+            assert_eq!(Gt::multipairing(&[(g1, &batched_projective[i])]), expected);
+        }
+
+        let g1s = (0..n)
+            .map(|_| G1Affine::from(G1Projective::random(&mut rng)))
+            .collect::<Vec<_>>();
+        let terms = |prepared: &[G2Prepared]| {
+            Gt::multipairing(&g1s.iter().zip(prepared).collect::<Vec<_>>())
+        };
+
+        assert_eq!(terms(&batched), terms(&individual));
+        assert_eq!(terms(&batched_projective), terms(&individual));
+    }
+}
+
 #[test]
 fn test_g1_deserialize_rejects_infinity_bit_with_nonzero_x() {
     let g1 = G1Affine::generator();
@@ -599,6 +1226,89 @@ fn test_scalar_serialization_round_trips() {
     }
 }
 
+#[test]
+fn test_scalar_modulus_and_generator_constants() {
+    assert!(Scalar::deserialize(&Scalar::MODULUS).is_err());
+    assert_eq!(
+        Scalar::deserialize_unchecked(&Scalar::MODULUS),
+        Scalar::zero()
+    );
+    assert_eq!(
+        hex::encode(Scalar::MODULUS),
+        "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001"
+    );
+
+    // The largest valid scalar is one less than the modulus
+    let mut modulus_minus_one = Scalar::MODULUS;
+    modulus_minus_one[Scalar::BYTES - 1] -= 1;
+    assert_eq!(
+        Scalar::deserialize(&modulus_minus_one).unwrap(),
+        Scalar::one().neg()
+    );
+
+    let generator = Scalar::MULTIPLICATIVE_GENERATOR;
+    assert!(!generator.is_zero());
+    assert_ne!(generator, Scalar::one());
+    assert_eq!(generator, Scalar::from_u64(7));
+}
+
+#[test]
+fn test_scalar_try_from_canonical_bytes() {
+    let mut rng = reproducible_rng();
+
+    for _ in 0..30 {
+        let s = Scalar::random(&mut rng);
+        assert_eq!(Scalar::try_from_canonical_bytes(s.serialize()), Ok(s));
+    }
+
+    for bytes in [Scalar::MODULUS, [0xFF; Scalar::BYTES]] {
+        assert_eq!(
+            Scalar::try_from_canonical_bytes(bytes),
+            Err(NonCanonicalScalar {
+                bytes,
+                reason: ScalarDeserError::OutOfRange
+            })
+        );
+    }
+}
+
+#[test]
+fn test_scalar_deserialize_detailed_errors() {
+    let mut rng = reproducible_rng();
+
+    assert_eq!(
+        Scalar::deserialize_detailed(&[0u8; 31]),
+        Err(ScalarDeserError::WrongLength { length: 31 })
+    );
+    assert_eq!(
+        Scalar::deserialize_detailed(&[0u8; 33]),
+        Err(ScalarDeserError::WrongLength { length: 33 })
+    );
+
+    let modulus =
+        hex::decode("73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001").unwrap();
+    assert_eq!(
+        Scalar::deserialize_detailed(&modulus),
+        Err(ScalarDeserError::OutOfRange)
+    );
+    assert_eq!(
+        Scalar::deserialize_detailed(&[0xFF; Scalar::BYTES]),
+        Err(ScalarDeserError::OutOfRange)
+    );
+    assert!(Scalar::deserialize(&modulus).is_err());
+
+    let modulus_minus_one = Scalar::one().neg().serialize();
+    assert_eq!(
+        Scalar::deserialize_detailed(&modulus_minus_one),
+        Ok(Scalar::one().neg())
+    );
+
+    for _ in 0..30 {
+        let s = Scalar::random(&mut rng);
+        assert_eq!(Scalar::deserialize_detailed(&s.serialize()), Ok(s));
+    }
+}
+
 #[test]
 fn test_g1_test_vectors() {
     /// The chosen generator for the G1 group.
@@ -1003,6 +1713,50 @@ fn test_verify_bls_signature_batch_with_distinct_msgs_and_sigs() {
     generic_test_verify_bls_signature_batch!(verify_bls_signature_batch_distinct);
 }
 
+#[test]
+fn test_verify_bls_signature_batch_distinct_parallel_matches_serial() {
+    let mut rng = reproducible_rng();
+    let rng_factory = || ChaCha20Rng::from_seed([42u8; 32]);
+
+    for num_inputs in [0, 1, 2, 3, 7, 16] {
+        let sks: Vec<_> = (0..num_inputs).map(|_| Scalar::random(&mut rng)).collect();
+        let pks: Vec<_> = sks
+            .iter()
+            .map(|sk| G2Affine::from(G2Affine::generator() * sk))
+            .collect();
+        let msgs: Vec<_> = (0..num_inputs)
+            .map(|_| G1Affine::hash(b"bls_signature", &rng.gen::<[u8; 32]>()))
+            .collect();
+        let sigs: Vec<_> = sks
+            .iter()
+            .zip(msgs.iter())
+            .map(|(sk, msg)| G1Affine::from(msg * sk))
+            .collect();
+
+        let mut batches = vec![(sigs.clone(), true)];
+        for i in 0..num_inputs {
+            let mut corrupted = sigs.clone();
+            corrupted[i] = G1Affine::hash(b"bls_signature", &rng.gen::<[u8; 32]>());
+            batches.push((corrupted, false));
+        }
+
+        for (sigs, expected) in batches {
+            let batch: Vec<_> = izip!(sigs.iter(), pks.iter(), msgs.iter()).collect();
+            assert_eq!(
+                verify_bls_signature_batch_distinct(&batch, &mut rng_factory()),
+                expected
+            );
+
+            for num_threads in [0, 1, 2, 4, 32] {
+                assert_eq!(
+                    verify_bls_signature_batch_distinct_parallel(&batch, num_threads, rng_factory),
+                    expected
+                );
+            }
+        }
+    }
+}
+
 #[test]
 fn test_verify_bls_signature_mixed_batch() {
     generic_test_verify_bls_signature_batch!(verify_bls_signature_batch);
@@ -1077,14 +1831,120 @@ fn test_verify_bls_signature_batch_with_same_msg() {
             &vec![msg.clone(); pks.len()][..],
         );
 
-        assert!(verify_bls_signature_batch_same_msg(
-            &sigs_w_dups
-                .iter()
-                .zip(pks_w_dups.iter())
-                .collect::<Vec<_>>()[..],
+        assert!(verify_bls_signature_batch_same_msg(
+            &sigs_w_dups
+                .iter()
+                .zip(pks_w_dups.iter())
+                .collect::<Vec<_>>()[..],
+            &msg,
+            &mut rng
+        ));
+    }
+}
+
+#[test]
+fn test_verify_bls_signature_batch_with_same_msg_agg() {
+    let mut rng = reproducible_rng();
+
+    assert_eq!(aggregate_public_keys(&[]), G2Affine::identity());
+
+    for num_inputs in [1, 2, 4, 16] {
+        let sks: Vec<_> = (0..num_inputs).map(|_| Scalar::random(&mut rng)).collect();
+        let pks: Vec<_> = sks
+            .iter()
+            .map(|sk| G2Affine::from(G2Affine::generator() * sk))
+            .collect();
+        let msg = G1Affine::hash(b"bls_signature", &rng.gen::<[u8; 32]>());
+        let sigs: Vec<_> = sks.iter().map(|sk| G1Affine::from(&msg * sk)).collect();
+        let sigs_pks: Vec<_> = sigs.iter().zip(pks.iter()).collect();
+
+        let expected_aggregate = aggregate_public_keys(&pks.iter().collect::<Vec<_>>());
+        let sum_of_sks = sks.iter().sum::<Scalar>();
+        assert_eq!(
+            expected_aggregate,
+            G2Affine::from(G2Affine::generator() * &sum_of_sks)
+        );
+
+        assert_eq!(
+            verify_bls_signature_batch_same_msg_agg(&sigs_pks, &msg, &mut rng),
+            Ok(expected_aggregate)
+        );
+
+        let mut bad_sigs = sigs.clone();
+        bad_sigs[num_inputs - 1] = G1Affine::hash(b"bls_signature", &rng.gen::<[u8; 32]>());
+        let bad_sigs_pks: Vec<_> = bad_sigs.iter().zip(pks.iter()).collect();
+        assert!(!verify_bls_signature_batch_same_msg(
+            &bad_sigs_pks,
             &msg,
             &mut rng
         ));
+        assert_eq!(
+            verify_bls_signature_batch_same_msg_agg(&bad_sigs_pks, &msg, &mut rng),
+            Err(BatchError::InvalidBatch)
+        );
+
+        let other_msg = G1Affine::hash(b"bls_signature", &rng.gen::<[u8; 32]>());
+        assert_eq!(
+            verify_bls_signature_batch_same_msg_agg(&sigs_pks, &other_msg, &mut rng),
+            Err(BatchError::InvalidBatch)
+        );
+    }
+}
+
+#[test]
+fn test_verify_bls_signature_batch_of_one_matches_single_verification() {
+    let mut rng = reproducible_rng();
+
+    for _ in 0..10 {
+        let sk = Scalar::random(&mut rng);
+        let pk = G2Affine::from(G2Affine::generator() * &sk);
+        let msg = G1Affine::hash(b"bls_signature", &rng.gen::<[u8; 32]>());
+        let sig = G1Affine::from(&msg * &sk);
+        let bad_sig = G1Affine::hash(b"bls_signature", &rng.gen::<[u8; 32]>());
+
+        for sig in [&sig, &bad_sig] {
+            let expected = verify_bls_signature(sig, &pk, &msg);
+            assert_eq!(expected, sig == &G1Affine::from(&msg * &sk));
+
+            // The single element path must not consume any randomness
+            let mut batch_rng = ChaCha20Rng::seed_from_u64(rng.gen());
+            let mut reference_rng = batch_rng.clone();
+
+            assert_eq!(
+                verify_bls_signature_batch_same_pk(&[(sig, &msg)], &pk, &mut batch_rng),
+                expected
+            );
+            assert_eq!(
+                verify_bls_signature_batch_same_msg(&[(sig, &pk)], &msg, &mut batch_rng),
+                expected
+            );
+            assert_eq!(
+                verify_bls_signature_batch_distinct(&[(sig, &pk, &msg)], &mut batch_rng),
+                expected
+            );
+            assert_eq!(
+                verify_bls_signature_batch(&[(sig, &pk, &msg)], &mut batch_rng),
+                expected
+            );
+            assert_eq!(batch_rng.gen::<u64>(), reference_rng.gen::<u64>());
+
+            // Repeating the element forces the randomized batched path
+            assert_eq!(
+                verify_bls_signature_batch_same_pk(&[(sig, &msg), (sig, &msg)], &pk, &mut rng),
+                expected
+            );
+            assert_eq!(
+                verify_bls_signature_batch_same_msg(&[(sig, &pk), (sig, &pk)], &msg, &mut rng),
+                expected
+            );
+            assert_eq!(
+                verify_bls_signature_batch_distinct(
+                    &[(sig, &pk, &msg), (sig, &pk, &msg)],
+                    &mut rng
+                ),
+                expected
+            );
+        }
     }
 }
 
@@ -1170,9 +2030,10 @@ fn test_verify_bls_signature_batch_with_same_pk() {
 #[test]
 fn test_hash_to_g1_matches_draft() {
     /*
-    These are the test vectors from draft-irtf-cfrg-hash-to-curve-16 section J.9.1
+    These are the test vectors from RFC 9380 section J.9.1, which are the
+    same as in draft-irtf-cfrg-hash-to-curve-16
 
-    The draft expresses the output in affine coordinates (x,y) while the
+    The RFC expresses the output in affine coordinates (x,y) while the
     BLS12-381 only exposes a compressed representation. In the BLS12-381
     compressed format the initial bit is set, as well the lowest bit of the
     leading byte may be set depending on the "sign" of y
@@ -1215,12 +2076,13 @@ fn test_hash_to_g1_matches_draft() {
 #[test]
 fn test_hash_to_g2_matches_draft() {
     /*
-    These are the test vectors from draft-irtf-cfrg-hash-to-curve-16 section J.9.1
+    These are the test vectors from RFC 9380 section J.10.1, which are the
+    same as in draft-irtf-cfrg-hash-to-curve-16
 
     As described in the test_hash_to_g1_matches_draft test, these are expressed
-    in compressed form, unlike the draft which uses uncompressed representation.
+    in compressed form, unlike the RFC which uses uncompressed representation.
 
-    The draft uses P.x = <a> + I * <b> where <a> and <b> are field elements.
+    The RFC uses P.x = <a> + I * <b> where <a> and <b> are field elements.
     However the serialization of G2 orders as <b> || <a> instead.
     */
 
@@ -1374,6 +2236,106 @@ test_point_operation!(serialization_round_trip, [g1, g2], {
     }
 });
 
+test_point_operation!(uncompressed_serialization_round_trip, [g1, g2], {
+    let mut rng = reproducible_rng();
+
+    let mut points = vec![Affine::identity(), Affine::generator().clone()];
+    for _ in 0..30 {
+        points.push(Affine::hash(
+            b"uncompressed-serialization-round-trip-test",
+            &rng.gen::<[u8; 32]>(),
+        ));
+    }
+
+    for pt in &points {
+        let bits = pt.serialize_uncompressed();
+        assert_eq!(bits.len(), Affine::UNCOMPRESSED_SIZE);
+        // The compression flag is clear, the infinity flag only set for the identity
+        assert_eq!(bits[0] & 0x80, 0);
+        assert_eq!(bits[0] & 0x40 != 0, pt.is_identity());
+
+        let d = Affine::deserialize_uncompressed(&bits).expect("Invalid serialization");
+        assert_eq!(&d, pt);
+        assert_eq!(d.serialize_uncompressed(), bits);
+
+        let du = Affine::deserialize_uncompressed_unchecked(&bits).expect("Invalid serialization");
+        assert_eq!(&du, pt);
+
+        // Both encodings decode to the same point
+        let compressed = pt.serialize();
+        assert_eq!(compressed[0] & 0x80, 0x80);
+        assert_eq!(Affine::deserialize(&compressed).unwrap(), d);
+        assert_eq!(&bits[1..Affine::BYTES], &compressed[1..]);
+        assert_eq!(bits[0] & 0x1F, compressed[0] & 0x1F);
+
+        assert!(Affine::deserialize_uncompressed(&compressed).is_err());
+        assert!(Affine::deserialize(&bits).is_err());
+        assert!(
+            Affine::deserialize_uncompressed(&bits[..Affine::UNCOMPRESSED_SIZE - 1].to_vec())
+                .is_err()
+        );
+    }
+
+    // A point which is on the curve but not within the prime-order subgroup
+    // is only accepted by the unchecked variant
+    let mut tested_non_torsion_free = false;
+    while !tested_non_torsion_free {
+        let mut buf = [0u8; Affine::BYTES];
+        rng.fill_bytes(&mut buf);
+        if let Ok(pt) = Affine::deserialize_unchecked(&buf) {
+            if !pt.is_torsion_free() {
+                let bits = pt.serialize_uncompressed();
+                assert!(Affine::deserialize_uncompressed(&bits).is_err());
+                let du = Affine::deserialize_uncompressed_unchecked(&bits).unwrap();
+                assert_eq!(du, pt);
+                tested_non_torsion_free = true;
+            }
+        }
+    }
+
+    // A point which is not on the curve is rejected by both variants
+    let mut bits = Affine::generator().serialize_uncompressed();
+    bits[Affine::UNCOMPRESSED_SIZE - 1] ^= 1;
+    assert!(Affine::deserialize_uncompressed(&bits).is_err());
+    assert!(Affine::deserialize_uncompressed_unchecked(&bits).is_err());
+});
+
+#[test]
+fn test_serialized_sizes() {
+    const _: () = assert!(Scalar::SERIALIZED_SIZE == 32);
+    const _: () = assert!(G1Affine::SERIALIZED_SIZE == 48);
+    const _: () = assert!(G1Affine::UNCOMPRESSED_SIZE == 96);
+    const _: () = assert!(G2Affine::SERIALIZED_SIZE == 96);
+    const _: () = assert!(G2Affine::UNCOMPRESSED_SIZE == 192);
+
+    let mut rng = reproducible_rng();
+
+    assert_eq!(
+        Scalar::random(&mut rng).serialize().len(),
+        Scalar::SERIALIZED_SIZE
+    );
+    assert_eq!(
+        G1Affine::hash(b"domain", b"input").serialize().len(),
+        G1Affine::SERIALIZED_SIZE
+    );
+    assert_eq!(
+        G2Affine::hash(b"domain", b"input").serialize().len(),
+        G2Affine::SERIALIZED_SIZE
+    );
+    assert_eq!(
+        G1Affine::hash(b"domain", b"input")
+            .serialize_uncompressed()
+            .len(),
+        G1Affine::UNCOMPRESSED_SIZE
+    );
+    assert_eq!(
+        G2Affine::hash(b"domain", b"input")
+            .serialize_uncompressed()
+            .len(),
+        G2Affine::UNCOMPRESSED_SIZE
+    );
+}
+
 test_point_operation!(is_torsion_free, [g1, g2], {
     let mut rng = reproducible_rng();
 
@@ -1407,6 +2369,149 @@ test_point_operation!(is_torsion_free, [g1, g2], {
     }
 });
 
+test_point_operation!(eq_affine, [g1, g2], {
+    let mut rng = reproducible_rng();
+
+    let identity = Projective::identity();
+    assert!(identity.eq_affine(&Affine::identity()));
+    assert!(!identity.eq_affine(Affine::generator()));
+    assert!(!Projective::generator().eq_affine(&Affine::identity()));
+
+    // An identity computed as p - p need not have the canonical representation
+    let pt = Projective::hash(b"domain_sep", b"input");
+    assert!((&pt - &pt).eq_affine(&Affine::identity()));
+
+    for _ in 0..30 {
+        let a = Projective::hash(b"domain_sep", &rng.gen::<[u8; 32]>());
+        let b = Projective::hash(b"domain_sep", &rng.gen::<[u8; 32]>());
+
+        assert_eq!(a.eq_affine(&b.to_affine()), a.to_affine() == b.to_affine());
+        assert!(a.eq_affine(&a.to_affine()));
+        assert!(!a.eq_affine(&b.to_affine()));
+
+        // The same point with a different Z coordinate
+        let a2 = &a.double() - &a;
+        assert!(a2.eq_affine(&a.to_affine()));
+        assert!(!a2.eq_affine(&a.neg().to_affine()));
+    }
+});
+
+test_point_operation!(random, [g1, g2], {
+    let mut rng = reproducible_rng();
+
+    let mut previous = Projective::random(&mut rng);
+
+    for _ in 0..30 {
+        let pt = Projective::random(&mut rng);
+        assert!(Affine::from(&pt).is_torsion_free());
+        assert!(!pt.is_identity());
+        assert_ne!(pt, previous);
+        previous = pt;
+    }
+});
+
+test_point_operation!(batch_from_projective, [g1, g2], {
+    let mut rng = reproducible_rng();
+
+    for count in [0, 1, 128] {
+        let mut points: Vec<_> = (0..count).map(|_| Projective::biased(&mut rng)).collect();
+        if count > 1 {
+            points[1] = Projective::identity();
+        }
+
+        let affine = Affine::batch_from_projective(&points);
+        assert_eq!(affine.len(), count);
+        for (a, p) in affine.iter().zip(points.iter()) {
+            assert_eq!(*a, p.to_affine());
+        }
+    }
+});
+
+test_point_operation!(double_in_place, [g1, g2], {
+    let mut rng = reproducible_rng();
+
+    let mut identity = Projective::identity();
+    identity.double_in_place();
+    assert_eq!(identity, Projective::identity());
+
+    for _ in 0..10 {
+        let pt = Projective::biased(&mut rng);
+        let mut doubled = pt.clone();
+        doubled.double_in_place();
+        assert_eq!(doubled, pt.double());
+        assert_eq!(doubled, &pt + &pt);
+    }
+});
+
+test_point_operation!(mul_windowed, [g1, g2], {
+    let mut rng = reproducible_rng();
+
+    let pt = Projective::biased(&mut rng);
+    let scalars = [
+        Scalar::zero(),
+        Scalar::one(),
+        Scalar::one().neg(),
+        Scalar::random(&mut rng),
+    ];
+
+    for window_bits in 1..=8 {
+        for s in &scalars {
+            assert_eq!(pt.mul_windowed(s, window_bits), &pt * s);
+        }
+        assert_eq!(
+            Projective::identity().mul_windowed(&scalars[3], window_bits),
+            Projective::identity()
+        );
+    }
+
+    for window_bits in [0, 9] {
+        let result = std::panic::catch_unwind(|| pt.mul_windowed(&scalars[3], window_bits));
+        assert!(result.is_err());
+    }
+});
+
+test_point_operation!(conditional_select_and_assign, [g1, g2], {
+    use subtle::Choice;
+
+    let mut rng = reproducible_rng();
+
+    for (a, b) in [
+        (Projective::identity(), Projective::biased(&mut rng)),
+        (Projective::biased(&mut rng), Projective::identity()),
+        (Projective::biased(&mut rng), Projective::biased(&mut rng)),
+    ] {
+        assert_eq!(Projective::conditional_select(&a, &b, Choice::from(0)), a);
+        assert_eq!(Projective::conditional_select(&a, &b, Choice::from(1)), b);
+
+        let mut x = a.clone();
+        x.conditional_assign(&b, Choice::from(0));
+        assert_eq!(x, a);
+        x.conditional_assign(&b, Choice::from(1));
+        assert_eq!(x, b);
+    }
+});
+
+test_point_operation!(mul_u64, [g1, g2], {
+    let mut rng = reproducible_rng();
+
+    let pt = Projective::random(&mut rng);
+
+    assert_eq!(pt.mul_u64(0), Projective::identity());
+    assert_eq!(pt.mul_u64(1), pt);
+    assert_eq!(pt.mul_u64(2), pt.double());
+
+    let mut small = Projective::identity();
+    for n in 0..100u64 {
+        assert_eq!(pt.mul_u64(n), small);
+        small += &pt;
+    }
+
+    let large = [u64::MAX, u64::MAX - 1, 1 << 63, (1 << 32) - 1, 1 << 32];
+    for n in large.into_iter().chain((0..30).map(|_| rng.gen::<u64>())) {
+        assert_eq!(pt.mul_u64(n), &pt * Scalar::from_u64(n));
+    }
+});
+
 test_point_operation!(negation, [g1, g2, gt], {
     assert_eq!(Affine::identity(), Affine::identity().neg());
     assert_eq!(Projective::identity(), Projective::identity().neg());
@@ -1423,6 +2528,93 @@ test_point_operation!(negation, [g1, g2, gt], {
     assert!((pt_pos + pt_neg).is_identity());
 });
 
+test_point_operation!(negate_in_place, [g1, g2], {
+    let mut identity = Projective::identity();
+    identity.negate();
+    assert_eq!(identity, Projective::identity());
+
+    let mut rng = reproducible_rng();
+
+    for _ in 0..30 {
+        let pt = Projective::biased(&mut rng);
+
+        let mut neg_pt = pt.clone();
+        neg_pt.negate();
+        assert_eq!(neg_pt, pt.neg());
+        assert_eq!(neg_pt.to_affine(), pt.to_affine().neg());
+        assert!((&pt + &neg_pt).is_identity());
+
+        neg_pt.negate();
+        assert_eq!(neg_pt, pt);
+    }
+});
+
+test_point_operation!(ct_is_identity, [g1, g2], {
+    assert!(bool::from(Projective::identity().ct_is_identity()));
+    assert!(bool::from(
+        Projective::from(Affine::identity()).ct_is_identity()
+    ));
+
+    let mut rng = reproducible_rng();
+
+    for _ in 0..30 {
+        let pt = Projective::biased(&mut rng);
+        let sum = &pt + &pt.neg();
+        assert!(bool::from(sum.ct_is_identity()));
+        assert_eq!(bool::from(pt.ct_is_identity()), pt.is_identity());
+        // Adding the identity must not change the result
+        let pt2 = &pt + &sum;
+        assert_eq!(bool::from(pt2.ct_is_identity()), pt.is_identity());
+    }
+
+    let g = Projective::generator();
+    assert!(!bool::from(g.ct_is_identity()));
+    let r = g * Scalar::random(&mut rng);
+    assert!(!bool::from(r.ct_is_identity()));
+});
+
+test_point_operation!(serialization_with_precompute, [g1, g2], {
+    let mut rng = reproducible_rng();
+
+    for _ in 0..5 {
+        let pt = Affine::hash(b"serialize-with-precompute", &rng.gen::<[u8; 32]>());
+        assert!(!pt.has_precomputation());
+
+        let bytes = pt.serialize_with_precompute();
+        let restored = Affine::deserialize_with_precompute(&bytes).expect("Valid encoding");
+
+        assert_eq!(restored, pt);
+        assert!(restored.has_precomputation());
+        assert_eq!(restored.serialize_with_precompute(), bytes);
+
+        for _ in 0..10 {
+            let s = Scalar::random(&mut rng);
+            assert_eq!(&restored * &s, &pt * &s);
+        }
+
+        // Truncated or corrupted inputs are rejected
+        assert!(Affine::deserialize_with_precompute(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Affine::deserialize_with_precompute(&bytes[..Affine::BYTES]).is_err());
+
+        let other = Affine::hash(b"serialize-with-precompute-other", &[]);
+        let mut mismatched = other.serialize().to_vec();
+        mismatched.extend_from_slice(&bytes[Affine::BYTES..]);
+        assert!(Affine::deserialize_with_precompute(&mismatched).is_err());
+
+        // A table element which is not on the curve is rejected
+        let mut off_curve = bytes.clone();
+        let last_byte_of_second_elem = Affine::BYTES + 2 * Affine::UNCOMPRESSED_SIZE - 1;
+        off_curve[last_byte_of_second_elem] ^= 1;
+        assert!(Affine::deserialize_with_precompute(&off_curve).is_err());
+    }
+
+    let identity = Affine::identity();
+    let restored = Affine::deserialize_with_precompute(&identity.serialize_with_precompute())
+        .expect("Valid encoding");
+    assert!(restored.is_identity());
+    assert!((&restored * &Scalar::random(&mut rng)).is_identity());
+});
+
 test_point_operation!(addition, [g1, g2, gt], {
     let mut rng = reproducible_rng();
 
@@ -1442,6 +2634,40 @@ test_point_operation!(addition, [g1, g2, gt], {
     }
 });
 
+test_point_operation!(assign_ops_by_reference, [g1, g2], {
+    let mut rng = reproducible_rng();
+
+    let points = (0..20)
+        .map(|_| Projective::biased(&mut rng))
+        .collect::<Vec<_>>();
+    let affine_points = Projective::batch_normalize(&points);
+
+    let mut acc = Projective::identity();
+    let mut neg_acc = Projective::identity();
+    let mut mixed_acc = Projective::identity();
+    for (p, a) in points.iter().zip(affine_points.iter()) {
+        acc += p;
+        neg_acc -= p;
+        mixed_acc += a;
+    }
+
+    let sum = points
+        .iter()
+        .fold(Projective::identity(), |accum, p| accum + p);
+    let neg_sum = points
+        .iter()
+        .fold(Projective::identity(), |accum, p| accum - p);
+    let mixed_sum = affine_points
+        .iter()
+        .fold(Projective::identity(), |accum, a| &accum + a);
+
+    assert_eq!(acc, sum);
+    assert_eq!(neg_acc, neg_sum);
+    assert_eq!(neg_acc, sum.neg());
+    assert_eq!(mixed_acc, mixed_sum);
+    assert_eq!(mixed_acc, sum);
+});
+
 test_point_operation!(sum, [g1, g2], {
     let mut rng = reproducible_rng();
 
@@ -1463,6 +2689,36 @@ test_point_operation!(sum, [g1, g2], {
     }
 });
 
+test_point_operation!(collect_sum, [g1, g2], {
+    let mut rng = reproducible_rng();
+
+    assert_eq!(
+        Vec::<Projective>::new().into_iter().collect::<Projective>(),
+        Projective::identity()
+    );
+    assert_eq!(
+        Vec::<Affine>::new().into_iter().collect::<Projective>(),
+        Projective::identity()
+    );
+
+    for t in [1, 2, 5, 16] {
+        let elements: Vec<Projective> = (0..t)
+            .map(|_| Projective::hash(b"domain_sep", &rng.gen::<[u8; 32]>()))
+            .collect();
+        let expected = elements
+            .iter()
+            .fold(Projective::identity(), |accum, pt| accum + pt);
+
+        assert_eq!(elements.iter().cloned().collect::<Projective>(), expected);
+        assert_eq!(
+            Projective::batch_normalize(&elements)
+                .into_iter()
+                .collect::<Projective>(),
+            expected
+        );
+    }
+});
+
 test_point_operation!(multiply, [g1, g2, gt], {
     let mut rng = reproducible_rng();
 
@@ -1529,6 +2785,12 @@ test_point_operation!(mul2, [g1, g2], {
     assert_eq!(Projective::mul2(g, &one, g, &zero), *g);
     assert_eq!(Projective::mul2(g, &zero, g, &one), *g);
 
+    let identity = Projective::identity();
+    let s = Scalar::biased(&mut rng);
+    assert_eq!(Projective::mul2(&identity, &s, &identity, &s), identity);
+    assert_eq!(Projective::mul2(&identity, &s, g, &one), *g);
+    assert_eq!(Projective::mul2(g, &s, &identity, &s), g * &s);
+
     for _ in 0..1000 {
         let s1 = Scalar::biased(&mut rng);
         let s2 = Scalar::biased(&mut rng);
@@ -1543,6 +2805,72 @@ test_point_operation!(mul2, [g1, g2], {
     }
 });
 
+test_point_operation!(mul2_table, [g1, g2], {
+    let mut rng = reproducible_rng();
+
+    let p1 = Projective::biased(&mut rng);
+    let p2 = Projective::biased(&mut rng);
+
+    let tbl = Projective::compute_mul2_tbl(&p1, &p2);
+
+    assert_eq!(
+        tbl.mul2(&Scalar::zero(), &Scalar::zero()),
+        Projective::identity()
+    );
+    assert_eq!(tbl.mul2(&Scalar::one(), &Scalar::zero()), p1);
+    assert_eq!(tbl.mul2(&Scalar::zero(), &Scalar::one()), p2);
+
+    for _ in 0..100 {
+        let s1 = Scalar::biased(&mut rng);
+        let s2 = Scalar::biased(&mut rng);
+
+        let reference = Projective::mul2(&p1, &s1, &p2, &s2);
+        assert_eq!(tbl.mul2(&s1, &s2), reference);
+        // Calling again with the same table gives the same result
+        assert_eq!(tbl.mul2(&s1, &s2), reference);
+    }
+
+    let a: [Scalar; 4] = std::array::from_fn(|_| Scalar::biased(&mut rng));
+    let b: [Scalar; 4] = std::array::from_fn(|_| Scalar::biased(&mut rng));
+
+    for (i, r) in tbl.mul2_array(&a, &b).iter().enumerate() {
+        assert_eq!(*r, Projective::mul2(&p1, &a[i], &p2, &b[i]));
+    }
+
+    let identity = Projective::identity();
+    let tbl = Projective::compute_mul2_tbl(&p1, &identity);
+    assert_eq!(tbl.mul2(&a[0], &b[0]), &p1 * &a[0]);
+    let tbl = Projective::compute_mul2_tbl(&identity, &identity);
+    assert_eq!(tbl.mul2(&a[0], &b[0]), identity);
+});
+
+test_point_operation!(mul2_sum, [g1, g2], {
+    let mut rng = reproducible_rng();
+
+    assert_eq!(Projective::mul2_sum(&[]), Projective::identity());
+
+    for term_count in [1, 2, 3, 10, 20, 50] {
+        let terms: Vec<_> = (0..term_count)
+            .map(|_| {
+                (
+                    Projective::biased(&mut rng).to_affine(),
+                    Scalar::biased(&mut rng),
+                    Projective::biased(&mut rng).to_affine(),
+                    Scalar::biased(&mut rng),
+                )
+            })
+            .collect();
+
+        let reference = terms
+            .iter()
+            .fold(Projective::identity(), |accum, (x, a, y, b)| {
+                accum + Projective::mul2(&x.into(), a, &y.into(), b)
+            });
+
+        assert_eq!(Projective::mul2_sum(&terms), reference);
+    }
+});
+
 test_point_operation!(muln_sparse, [g1, g2], {
     let mut rng = reproducible_rng();
 
@@ -1643,6 +2971,90 @@ test_point_operation!(muln, [g1, g2], {
     }
 });
 
+#[test]
+fn test_scalar_precompute_mul() {
+    let mut rng = reproducible_rng();
+
+    let mut scalars = Scalar::batch_random(&mut rng, 10);
+    scalars.extend([Scalar::zero(), Scalar::one(), Scalar::one().neg()]);
+
+    for scalar in &scalars {
+        let tbl = scalar.precompute_mul();
+
+        for _ in 0..5 {
+            let g1 = G1Affine::from(G1Projective::biased(&mut rng));
+            assert_eq!(tbl.mul_g1(&g1), &g1 * scalar);
+            let g2 = G2Affine::from(G2Projective::biased(&mut rng));
+            assert_eq!(tbl.mul_g2(&g2), &g2 * scalar);
+        }
+
+        assert_eq!(tbl.mul_g1(&G1Affine::identity()), G1Projective::identity());
+        assert_eq!(tbl.mul_g2(&G2Affine::identity()), G2Projective::identity());
+        assert_eq!(
+            tbl.mul_g1(G1Affine::generator()),
+            G1Projective::generator() * scalar
+        );
+    }
+}
+
+#[test]
+fn test_g1_sum_of_products_with_partials() {
+    let mut rng = reproducible_rng();
+
+    let (total, partials) = G1Projective::sum_of_products_with_partials(&[], &[]);
+    assert_eq!(total, G1Projective::identity());
+    assert!(partials.is_empty());
+
+    for t in [1, 2, 3, 20, 70] {
+        let points: Vec<_> = (0..t).map(|_| G1Projective::biased(&mut rng)).collect();
+        let scalars = Scalar::batch_random(&mut rng, t);
+
+        let (total, partials) = G1Projective::sum_of_products_with_partials(&points, &scalars);
+        assert_eq!(total, G1Projective::muln_vartime(&points, &scalars));
+        assert_eq!(partials.len(), t);
+        assert_eq!(partials.last(), Some(&total));
+
+        assert_eq!(partials[0], &points[0] * &scalars[0]);
+        for i in 1..t {
+            assert_eq!(partials[i], &partials[i - 1] + &(&points[i] * &scalars[i]));
+        }
+    }
+
+    // Only as many terms as the shorter input are used.
+    let points: Vec<_> = (0..5).map(|_| G1Projective::biased(&mut rng)).collect();
+    let scalars = Scalar::batch_random(&mut rng, 3);
+    let (total, partials) = G1Projective::sum_of_products_with_partials(&points, &scalars);
+    assert_eq!(partials.len(), 3);
+    assert_eq!(total, G1Projective::muln_vartime(&points[..3], &scalars));
+}
+
+#[test]
+fn test_gt_muln() {
+    let mut rng = reproducible_rng();
+
+    assert_eq!(Gt::muln_vartime(&[], &[]), Gt::identity());
+
+    // Covers the naive path and both window sizes
+    for t in [1, 2, 3, 7, 8, 9, 20, 63, 64, 70] {
+        let mut points = Vec::with_capacity(t);
+        let mut scalars = Vec::with_capacity(t);
+
+        for _ in 0..t {
+            points.push(Gt::generator() * Scalar::random(&mut rng));
+            scalars.push(Scalar::biased(&mut rng));
+        }
+
+        let reference_val = points
+            .iter()
+            .zip(scalars.iter())
+            .fold(Gt::identity(), |accum, (p, s)| accum + p * s);
+
+        let computed = Gt::muln_vartime(&points[..], &scalars[..]);
+
+        assert_eq!(computed, reference_val);
+    }
+}
+
 test_point_operation!(batch_normalize, [g1, g2], {
     let mut rng = reproducible_rng();
 
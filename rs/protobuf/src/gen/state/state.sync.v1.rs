@@ -22,6 +22,14 @@ pub struct ChunkInfo {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ManifestMetadata {
+    #[prost(uint64, tag = "1")]
+    pub checkpoint_height: u64,
+    #[prost(string, tag = "2")]
+    pub label: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Manifest {
     #[prost(uint32, tag = "1")]
     pub version: u32,
@@ -29,6 +37,9 @@ pub struct Manifest {
     pub file_table: ::prost::alloc::vec::Vec<FileInfo>,
     #[prost(message, repeated, tag = "3")]
     pub chunk_table: ::prost::alloc::vec::Vec<ChunkInfo>,
+    /// Not covered by the manifest hash.
+    #[prost(message, optional, tag = "4")]
+    pub metadata: ::core::option::Option<ManifestMetadata>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
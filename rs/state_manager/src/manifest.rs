@@ -8,7 +8,10 @@ mod tests {
 
 use super::CheckpointError;
 use crate::{
-    manifest::hash::{meta_manifest_hasher, sub_manifest_hasher},
+    manifest::hash::{
+        merkle_leaf_hasher, merkle_node_hasher, merkle_root_hasher, meta_manifest_hasher,
+        sub_manifest_hasher,
+    },
     BundledManifest, DirtyPages, FileType, ManifestMetrics,
     CRITICAL_ERROR_CHUNK_ID_USAGE_NEARING_LIMITS, CRITICAL_ERROR_REUSED_CHUNK_HASH,
     LABEL_VALUE_HASHED, LABEL_VALUE_HASHED_AND_COMPARED, LABEL_VALUE_REUSED,
@@ -19,18 +22,20 @@ use hash::{chunk_hasher, file_hasher, manifest_hasher, ManifestHash};
 use ic_crypto_sha::Sha256;
 use ic_logger::{error, fatal, replica_logger::no_op_logger, ReplicaLogger};
 use ic_metrics::MetricsRegistry;
+use ic_protobuf::state::sync::v1 as pb;
 use ic_replicated_state::PageIndex;
 use ic_state_layout::{CheckpointLayout, ReadOnly};
 use ic_sys::{mmap::ScopedMmap, PAGE_SIZE};
 use ic_types::{
     crypto::CryptoHash,
     state_sync::{
-        encode_manifest, ChunkInfo, FileGroupChunks, FileInfo, Manifest, MetaManifest,
-        StateSyncVersion, FILE_CHUNK_ID_OFFSET, FILE_GROUP_CHUNK_ID_OFFSET,
+        encode_manifest, encode_manifest_without_metadata, ChunkInfo, FileGroupChunks, FileInfo,
+        Manifest, MetaManifest, StateSyncVersion, FILE_CHUNK_ID_OFFSET, FILE_GROUP_CHUNK_ID_OFFSET,
         MAX_SUPPORTED_STATE_SYNC_VERSION,
     },
     CryptoHashOfState, Height,
 };
+use prost::Message;
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaChaRng;
 use std::collections::{BTreeMap, HashMap, HashSet};
@@ -78,6 +83,13 @@ pub enum ManifestValidationError {
         manifest_version: StateSyncVersion,
         max_supported_version: StateSyncVersion,
     },
+    InvalidZeroLengthFile {
+        relative_path: PathBuf,
+        num_chunks: usize,
+    },
+    DuplicatePath {
+        relative_path: PathBuf,
+    },
 }
 
 impl fmt::Display for ManifestValidationError {
@@ -111,6 +123,20 @@ impl fmt::Display for ManifestValidationError {
                 "manifest version {} not supported, maximum supported version {}",
                 manifest_version, max_supported_version,
             ),
+            Self::InvalidZeroLengthFile {
+                relative_path,
+                num_chunks,
+            } => write!(
+                f,
+                "zero-length file {} has {} chunks, expected none",
+                relative_path.display(),
+                num_chunks
+            ),
+            Self::DuplicatePath { relative_path } => write!(
+                f,
+                "file {} appears more than once in the manifest",
+                relative_path.display()
+            ),
         }
     }
 }
@@ -172,6 +198,44 @@ impl fmt::Display for ChunkValidationError {
 
 impl std::error::Error for ChunkValidationError {}
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum SubsetError {
+    PathNotFound { relative_path: PathBuf },
+}
+
+impl fmt::Display for SubsetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PathNotFound { relative_path } => write!(
+                f,
+                "file {} is not present in the manifest",
+                relative_path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SubsetError {}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MergeError {
+    DuplicatePath { relative_path: PathBuf },
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicatePath { relative_path } => write!(
+                f,
+                "file {} is present in more than one partial manifest",
+                relative_path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MergeError {}
+
 /// Relative path to a file and the size of the file.
 #[derive(Clone)]
 struct FileWithSize(PathBuf, u64);
@@ -214,6 +278,48 @@ pub struct DiffScript {
     pub(crate) zeros_chunks: u32,
 }
 
+/// Proof that a file table entry is part of a manifest with a given root hash.
+///
+/// Only manifests of version `StateSyncVersion::V2` and above, whose root hash
+/// is the meta-manifest hash, support inclusion proofs. The proof consists of
+/// all sub-manifest hashes and the raw bytes of the sub-manifests from the
+/// start of the encoded manifest up to the end of the encoded file table entry.
+/// Starting at byte 0 lets the verifier walk the preceding entries, so that the
+/// entry is known to be the one at `file_index` rather than bytes that merely
+/// look like an entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileInclusionProof {
+    /// Version of the manifest.
+    pub version: StateSyncVersion,
+    /// Hashes of all sub-manifests, as in the meta-manifest.
+    pub sub_manifest_hashes: Vec<[u8; 32]>,
+    /// Index of the file in the file table.
+    pub file_index: usize,
+    /// Bytes of the sub-manifests up to the one containing the end of the file
+    /// table entry, starting with the first sub-manifest.
+    pub sub_manifest_bytes: Vec<u8>,
+    /// Offset of the encoded file table entry within `sub_manifest_bytes`.
+    pub offset: usize,
+}
+
+/// Proof that a file table entry is a leaf of the Merkle tree over the file
+/// table of a manifest, see `merkle_root`.
+///
+/// The proof contains one sibling hash per level of the tree, so its size is
+/// logarithmic in the number of files.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleProof {
+    /// Version of the manifest.
+    pub version: StateSyncVersion,
+    /// Index of the file in the file table.
+    pub file_index: usize,
+    /// Number of files in the file table.
+    pub num_files: usize,
+    /// Sibling hashes on the path from the leaf to the root, starting at the
+    /// leaf.
+    pub siblings: Vec<[u8; 32]>,
+}
+
 /// ManifestDelta contains a manifest of an old state and indices of all the
 /// memory pages that changed (became "dirty") since that state.
 ///
@@ -271,8 +377,7 @@ pub(crate) fn build_file_group_chunks(manifest: &Manifest) -> FileGroupChunks {
 }
 
 fn write_chunk_hash(hasher: &mut Sha256, chunk_info: &ChunkInfo, version: StateSyncVersion) {
-    // Starting with `V3`, no longer include file index in chunk/file hash.
-    if version < StateSyncVersion::V3 {
+    if version.hashes_include_file_index() {
         chunk_info.file_index.update_hash(hasher);
     }
     chunk_info.size_bytes.update_hash(hasher);
@@ -280,6 +385,99 @@ fn write_chunk_hash(hasher: &mut Sha256, chunk_info: &ChunkInfo, version: StateS
     chunk_info.hash.update_hash(hasher);
 }
 
+/// Computes the hash of a file from the chunk table entries belonging to it.
+/// See note [Manifest Hash].
+fn file_hash(chunks: &[ChunkInfo], version: StateSyncVersion) -> [u8; 32] {
+    let mut hasher = file_hasher();
+    (chunks.len() as u32).update_hash(&mut hasher);
+    for chunk_info in chunks {
+        write_chunk_hash(&mut hasher, chunk_info, version);
+    }
+    hasher.finish()
+}
+
+/// Computes the file table entry and the chunk table entries of a single file
+/// from its contents, splitting it into chunks of `DEFAULT_CHUNK_SIZE`.
+///
+/// The contents are taken as a slice so that callers can pass memory-mapped
+/// regions directly. `file_index` is the index the file will have in the file
+/// table; it is recorded in the chunk entries and, before
+/// `StateSyncVersion::V3`, it is also part of the file hash. Composing the
+/// results for all files in sorted path order yields the same tables as
+/// `compute_manifest`.
+pub fn compute_file_info(
+    file_index: u32,
+    path: &Path,
+    contents: &[u8],
+    version: StateSyncVersion,
+) -> (FileInfo, Vec<ChunkInfo>) {
+    compute_file_info_with_chunk_size(file_index, path, contents, DEFAULT_CHUNK_SIZE, version)
+}
+
+/// Recomputes the file table entry and the chunk table entries of a file,
+/// splitting its `contents` into chunks of `new_chunk_size`.
+///
+/// This is used to migrate a manifest to a different chunk size: the result is
+/// the same as the one `compute_manifest` produces for the file when called
+/// with `max_chunk_size` set to `new_chunk_size`. The chunk table does not
+/// record which file an entry belongs to by path, so `file_index` has to be
+/// passed explicitly.
+///
+/// Panics if `contents` does not have the size recorded in `file_info`.
+pub fn rechunk_file(
+    file_index: u32,
+    file_info: &FileInfo,
+    contents: &[u8],
+    new_chunk_size: u32,
+    version: StateSyncVersion,
+) -> (FileInfo, Vec<ChunkInfo>) {
+    assert_eq!(
+        file_info.size_bytes,
+        contents.len() as u64,
+        "contents of file {} do not match the size in the file table",
+        file_info.relative_path.display()
+    );
+    assert!(new_chunk_size > 0, "chunk size must be positive");
+    compute_file_info_with_chunk_size(
+        file_index,
+        &file_info.relative_path,
+        contents,
+        new_chunk_size,
+        version,
+    )
+}
+
+fn compute_file_info_with_chunk_size(
+    file_index: u32,
+    path: &Path,
+    contents: &[u8],
+    chunk_size: u32,
+    version: StateSyncVersion,
+) -> (FileInfo, Vec<ChunkInfo>) {
+    let chunks: Vec<ChunkInfo> = contents
+        .chunks(chunk_size as usize)
+        .enumerate()
+        .map(|(i, data)| {
+            let mut hasher = chunk_hasher();
+            hasher.write(data);
+            ChunkInfo {
+                file_index,
+                size_bytes: data.len() as u32,
+                offset: i as u64 * chunk_size as u64,
+                hash: hasher.finish(),
+            }
+        })
+        .collect();
+
+    let file_info = FileInfo {
+        relative_path: path.to_path_buf(),
+        size_bytes: contents.len() as u64,
+        hash: file_hash(&chunks, version),
+    };
+
+    (file_info, chunks)
+}
+
 /// Returns the number of chunks of size `max_chunk_size` required to cover a
 /// file of size `size_bytes`.
 fn count_chunks(size_bytes: u64, max_chunk_size: u32) -> usize {
@@ -928,6 +1126,10 @@ pub fn validate_manifest(
         });
     }
 
+    if let Some(relative_path) = manifest.find_duplicate_paths().into_iter().next() {
+        return Err(ManifestValidationError::DuplicatePath { relative_path });
+    }
+
     let mut chunk_start: usize = 0;
 
     for (file_index, f) in manifest.file_table.iter().enumerate() {
@@ -938,6 +1140,15 @@ pub fn validate_manifest(
             .take_while(|chunk| chunk.file_index as usize == file_index)
             .count();
 
+        // A zero-length file has no chunks, not even empty ones, so its hash is
+        // that of an empty slice of the chunk table. See note [Manifest Hash].
+        if f.size_bytes == 0 && chunk_count != 0 {
+            return Err(ManifestValidationError::InvalidZeroLengthFile {
+                relative_path: f.relative_path.clone(),
+                num_chunks: chunk_count,
+            });
+        }
+
         (chunk_count as u32).update_hash(&mut hasher);
 
         for chunk_info in manifest.chunk_table[chunk_start..chunk_start + chunk_count].iter() {
@@ -970,6 +1181,32 @@ pub fn validate_manifest(
     Ok(())
 }
 
+/// Recomputes the hash of every file from its chunk table entries, following
+/// the hashing rules of the manifest's version, and returns the indices of the
+/// files whose recorded hash doesn't match.
+///
+/// Unlike `validate_manifest`, this doesn't require the expected root hash, so
+/// it can be used right after decoding a manifest to check that its file and
+/// chunk tables are consistent with each other.
+pub fn verify_file_hashes(manifest: &Manifest) -> Result<(), Vec<u32>> {
+    let mismatched: Vec<u32> = manifest
+        .file_table
+        .iter()
+        .enumerate()
+        .filter(|(file_index, f)| {
+            let chunk_range = file_chunk_range(&manifest.chunk_table, *file_index);
+            file_hash(&manifest.chunk_table[chunk_range], manifest.version) != f.hash
+        })
+        .map(|(file_index, _)| file_index as u32)
+        .collect();
+
+    if mismatched.is_empty() {
+        Ok(())
+    } else {
+        Err(mismatched)
+    }
+}
+
 /// Checks that the size and hash of the received chunk match the chunk table of
 /// the manifest.
 pub fn validate_chunk(
@@ -1042,6 +1279,51 @@ pub fn manifest_hash(manifest: &Manifest) -> [u8; 32] {
     }
 }
 
+/// A manifest that is compared and hashed by its root hash.
+///
+/// The derived `PartialEq` and `Hash` of `Manifest` compare the in-memory
+/// representation, which includes the metadata not covered by the root hash.
+/// Keying a collection by `ContentKeyedManifest` instead treats manifests
+/// with the same root hash as equal.
+#[derive(Clone, Debug)]
+pub struct ContentKeyedManifest {
+    manifest: Manifest,
+    content_hash: [u8; 32],
+}
+
+impl ContentKeyedManifest {
+    pub fn new(manifest: Manifest) -> Self {
+        let content_hash = manifest_hash(&manifest);
+        Self {
+            manifest,
+            content_hash,
+        }
+    }
+
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    /// Returns the root hash of the manifest, see `manifest_hash`.
+    pub fn content_hash(&self) -> [u8; 32] {
+        self.content_hash
+    }
+}
+
+impl PartialEq for ContentKeyedManifest {
+    fn eq(&self, other: &Self) -> bool {
+        self.content_hash == other.content_hash
+    }
+}
+
+impl Eq for ContentKeyedManifest {}
+
+impl std::hash::Hash for ContentKeyedManifest {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.content_hash.hash(state);
+    }
+}
+
 fn manifest_hash_v1(manifest: &Manifest) -> [u8; 32] {
     assert!(manifest.version <= StateSyncVersion::V1);
 
@@ -1076,10 +1358,13 @@ fn manifest_hash_v1(manifest: &Manifest) -> [u8; 32] {
 }
 
 /// Builds meta-manifest from a manifest by encoding, splitting and hashing.
+///
+/// The manifest metadata is left out of the encoding, so that it does not
+/// affect the manifest hash.
 pub fn build_meta_manifest(manifest: &Manifest) -> MetaManifest {
     let mut sub_manifest_hashes = Vec::new();
 
-    let encoded_manifest = encode_manifest(manifest);
+    let encoded_manifest = encode_manifest_without_metadata(manifest);
     let size_bytes = encoded_manifest.len();
     let mut bytes_left = size_bytes;
     let mut hashed_bytes = 0;
@@ -1125,6 +1410,275 @@ fn manifest_hash_v2(manifest: &Manifest) -> [u8; 32] {
     meta_manifest_hash(&meta_manifest)
 }
 
+/// Encodes a single file table entry exactly as it appears in the encoded
+/// manifest, i.e. including the field tag and length prefix.
+fn encode_file_table_entry(file_info: &FileInfo) -> Vec<u8> {
+    pb::Manifest {
+        version: 0,
+        file_table: vec![file_info.clone().into()],
+        chunk_table: vec![],
+        metadata: None,
+    }
+    .encode_to_vec()
+}
+
+/// Builds a proof that the file at `file_index` is included in the manifest.
+///
+/// Returns `None` if the manifest version does not use the meta-manifest hash
+/// as root hash or if `file_index` is out of bounds.
+pub fn hash_tree_proof(manifest: &Manifest, file_index: usize) -> Option<FileInclusionProof> {
+    if manifest.version < StateSyncVersion::V2 || file_index >= manifest.file_table.len() {
+        return None;
+    }
+
+    // Fields are encoded in the order of their tags, so the file table entries
+    // directly follow the version.
+    let start = pb::Manifest {
+        version: manifest.version as u32,
+        file_table: manifest.file_table[..file_index]
+            .iter()
+            .cloned()
+            .map(pb::FileInfo::from)
+            .collect(),
+        chunk_table: vec![],
+        metadata: None,
+    }
+    .encoded_len();
+    let end = start + encode_file_table_entry(&manifest.file_table[file_index]).len();
+
+    let encoded_manifest = encode_manifest_without_metadata(manifest);
+    let chunk_size = DEFAULT_CHUNK_SIZE as usize;
+    let last_sub_manifest = (end - 1) / chunk_size;
+    let bytes_end = encoded_manifest
+        .len()
+        .min((last_sub_manifest + 1) * chunk_size);
+
+    Some(FileInclusionProof {
+        version: manifest.version,
+        sub_manifest_hashes: build_meta_manifest(manifest).sub_manifest_hashes,
+        file_index,
+        sub_manifest_bytes: encoded_manifest[..bytes_end].to_vec(),
+        offset: start,
+    })
+}
+
+/// Returns the offset of the file table entry at `file_index` within
+/// `encoded_manifest`, a prefix of an encoded manifest, by walking the fields
+/// from the start. Returns `None` if the prefix is malformed, ends before the
+/// entry or contains a field other than the version before it.
+fn file_table_entry_offset(encoded_manifest: &[u8], file_index: usize) -> Option<usize> {
+    use prost::encoding::{decode_key, decode_varint, WireType};
+
+    let mut bytes = encoded_manifest;
+    let mut index = 0;
+    while !bytes.is_empty() {
+        let offset = encoded_manifest.len() - bytes.len();
+        match decode_key(&mut bytes).ok()? {
+            // The version.
+            (1, WireType::Varint) => {
+                decode_varint(&mut bytes).ok()?;
+            }
+            // A file table entry.
+            (2, WireType::LengthDelimited) => {
+                if index == file_index {
+                    return Some(offset);
+                }
+                let len = usize::try_from(decode_varint(&mut bytes).ok()?).ok()?;
+                bytes = bytes.get(len..)?;
+                index += 1;
+            }
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// Checks that `proof` shows `file_info` to be the file table entry at
+/// `proof.file_index` of a manifest with the given root hash.
+pub fn verify_file_inclusion(
+    root_hash: &CryptoHashOfState,
+    proof: &FileInclusionProof,
+    file_info: &FileInfo,
+) -> bool {
+    if proof.version < StateSyncVersion::V2 || proof.version > MAX_SUPPORTED_STATE_SYNC_VERSION {
+        return false;
+    }
+
+    let meta_manifest = MetaManifest {
+        version: proof.version,
+        sub_manifest_hashes: proof.sub_manifest_hashes.clone(),
+    };
+    if root_hash.get_ref().0 != meta_manifest_hash(&meta_manifest) {
+        return false;
+    }
+
+    if proof.sub_manifest_bytes.is_empty() {
+        return false;
+    }
+    for (i, sub_manifest) in proof
+        .sub_manifest_bytes
+        .chunks(DEFAULT_CHUNK_SIZE as usize)
+        .enumerate()
+    {
+        let expected_hash = match proof.sub_manifest_hashes.get(i) {
+            Some(hash) => hash,
+            None => return false,
+        };
+        let mut hasher = sub_manifest_hasher();
+        hasher.write(sub_manifest);
+        if hasher.finish() != *expected_hash {
+            return false;
+        }
+    }
+
+    if file_table_entry_offset(&proof.sub_manifest_bytes, proof.file_index) != Some(proof.offset) {
+        return false;
+    }
+
+    let entry = encode_file_table_entry(file_info);
+    proof
+        .offset
+        .checked_add(entry.len())
+        .and_then(|end| proof.sub_manifest_bytes.get(proof.offset..end))
+        == Some(&entry[..])
+}
+
+fn merkle_leaf_hash(file_info: &FileInfo) -> [u8; 32] {
+    let mut hasher = merkle_leaf_hasher();
+    let path = file_info
+        .relative_path
+        .to_str()
+        .expect("failed to convert path to a str");
+    path.update_hash(&mut hasher);
+    file_info.size_bytes.update_hash(&mut hasher);
+    file_info.hash.update_hash(&mut hasher);
+    hasher.finish()
+}
+
+fn merkle_node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = merkle_node_hasher();
+    left.update_hash(&mut hasher);
+    right.update_hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Returns the size of the left subtree of a tree with `n > 1` leaves, i.e.
+/// the largest power of two smaller than `n`.
+fn merkle_split(n: usize) -> usize {
+    debug_assert!(n > 1);
+    let mut k = 1;
+    while k << 1 < n {
+        k <<= 1;
+    }
+    k
+}
+
+fn merkle_tree_hash(leaves: &[[u8; 32]]) -> [u8; 32] {
+    match leaves.len() {
+        0 => merkle_node_hasher().finish(),
+        1 => leaves[0],
+        n => {
+            let k = merkle_split(n);
+            merkle_node_hash(
+                &merkle_tree_hash(&leaves[..k]),
+                &merkle_tree_hash(&leaves[k..]),
+            )
+        }
+    }
+}
+
+fn merkle_path(leaves: &[[u8; 32]], index: usize, siblings: &mut Vec<[u8; 32]>) {
+    let n = leaves.len();
+    if n <= 1 {
+        return;
+    }
+    let k = merkle_split(n);
+    if index < k {
+        merkle_path(&leaves[..k], index, siblings);
+        siblings.push(merkle_tree_hash(&leaves[k..]));
+    } else {
+        merkle_path(&leaves[k..], index - k, siblings);
+        siblings.push(merkle_tree_hash(&leaves[..k]));
+    }
+}
+
+fn merkle_root_from_tree_hash(
+    version: StateSyncVersion,
+    num_files: usize,
+    tree_hash: &[u8; 32],
+) -> [u8; 32] {
+    let mut hasher = merkle_root_hasher();
+    version.update_hash(&mut hasher);
+    (num_files as u32).update_hash(&mut hasher);
+    tree_hash.update_hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Computes the root of a binary Merkle tree whose leaves are the file table
+/// entries of the manifest.
+///
+/// The tree has the same shape as the trees of RFC 9162: the left subtree of a
+/// node with `n` leaves holds the largest power of two smaller than `n`
+/// leaves. The root also commits to the manifest version and the number of
+/// files. This is an additional commitment that comes with logarithmic
+/// inclusion proofs (see `merkle_proof`); it does not replace the manifest
+/// hash returned by `manifest_hash` for any version.
+pub fn merkle_root(manifest: &Manifest) -> [u8; 32] {
+    let leaves: Vec<[u8; 32]> = manifest.file_table.iter().map(merkle_leaf_hash).collect();
+    merkle_root_from_tree_hash(manifest.version, leaves.len(), &merkle_tree_hash(&leaves))
+}
+
+/// Builds a proof that the file at `file_index` is included in the Merkle tree
+/// of the manifest, or returns `None` if `file_index` is out of bounds.
+pub fn merkle_proof(manifest: &Manifest, file_index: usize) -> Option<MerkleProof> {
+    if file_index >= manifest.file_table.len() {
+        return None;
+    }
+    let leaves: Vec<[u8; 32]> = manifest.file_table.iter().map(merkle_leaf_hash).collect();
+    let mut siblings = Vec::new();
+    merkle_path(&leaves, file_index, &mut siblings);
+    Some(MerkleProof {
+        version: manifest.version,
+        file_index,
+        num_files: leaves.len(),
+        siblings,
+    })
+}
+
+/// Checks that `proof` shows `file_info` to be part of a manifest with the
+/// given Merkle root, as computed by `merkle_root`.
+pub fn verify_merkle_proof(root: &[u8; 32], proof: &MerkleProof, file_info: &FileInfo) -> bool {
+    if proof.file_index >= proof.num_files || proof.num_files > u32::MAX as usize {
+        return false;
+    }
+
+    // Walk up from the leaf, following RFC 9162, section 2.1.3.2.
+    let mut index = proof.file_index;
+    let mut last_index = proof.num_files - 1;
+    let mut hash = merkle_leaf_hash(file_info);
+    for sibling in &proof.siblings {
+        if last_index == 0 {
+            return false;
+        }
+        if index & 1 == 1 || index == last_index {
+            hash = merkle_node_hash(sibling, &hash);
+            while index & 1 == 0 && index != 0 {
+                index >>= 1;
+                last_index >>= 1;
+            }
+        } else {
+            hash = merkle_node_hash(&hash, sibling);
+        }
+        index >>= 1;
+        last_index >>= 1;
+    }
+    if last_index != 0 {
+        return false;
+    }
+
+    merkle_root_from_tree_hash(proof.version, proof.num_files, &hash) == *root
+}
+
 /// Computes the bundled metadata from a manifest.
 pub(crate) fn compute_bundled_manifest(manifest: Manifest) -> BundledManifest {
     let meta_manifest = build_meta_manifest(&manifest);
@@ -1259,6 +1813,274 @@ pub fn diff_manifest(
     }
 }
 
+/// Renders a human-readable report of how `manifest_new` differs from
+/// `manifest_old`, for operators debugging divergent states.
+///
+/// Files are listed in the order of the new file table followed by removed
+/// files, each marked with `+` (added), `-` (removed) or `~` (changed).
+/// Changed files are followed by the chunks that differ from the chunk at the
+/// same offset in the old file; changed chunks that cannot be copied from the
+/// old state according to `diff_manifest` are marked as `(fetch)`. Hashes are
+/// shown as hex prefixes. The report is empty if the manifests are identical.
+pub fn diff_report(manifest_old: &Manifest, manifest_new: &Manifest) -> String {
+    use std::fmt::Write;
+
+    fn short_hash(hash: &[u8; 32]) -> String {
+        hex::encode(&hash[..8])
+    }
+
+    let diff = diff_manifest(manifest_old, &HashSet::new(), manifest_new);
+    let old_files: HashMap<&Path, usize> = manifest_old
+        .file_table
+        .iter()
+        .enumerate()
+        .map(|(index, f)| (f.relative_path.as_path(), index))
+        .collect();
+    let new_paths: HashSet<&Path> = manifest_new
+        .file_table
+        .iter()
+        .map(|f| f.relative_path.as_path())
+        .collect();
+
+    let mut report = String::new();
+    if manifest_old.version != manifest_new.version {
+        writeln!(
+            report,
+            "~ version {} -> {}",
+            manifest_old.version, manifest_new.version
+        )
+        .unwrap();
+    }
+
+    for (new_index, new_file) in manifest_new.file_table.iter().enumerate() {
+        let old_index = match old_files.get(new_file.relative_path.as_path()) {
+            Some(old_index) => *old_index,
+            None => {
+                writeln!(report, "+ {}", new_file.to_compact_string()).unwrap();
+                continue;
+            }
+        };
+        let old_file = &manifest_old.file_table[old_index];
+        if old_file == new_file {
+            continue;
+        }
+
+        writeln!(
+            report,
+            "~ {} size {} -> {} hash {} -> {}",
+            new_file.relative_path.display(),
+            old_file.size_bytes,
+            new_file.size_bytes,
+            short_hash(&old_file.hash),
+            short_hash(&new_file.hash)
+        )
+        .unwrap();
+
+        let old_chunk_range = file_chunk_range(&manifest_old.chunk_table, old_index);
+        let old_chunks: BTreeMap<u64, &ChunkInfo> = manifest_old.chunk_table[old_chunk_range]
+            .iter()
+            .map(|c| (c.offset, c))
+            .collect();
+        let new_chunk_range = file_chunk_range(&manifest_new.chunk_table, new_index);
+        for chunk_index in new_chunk_range.clone() {
+            let new_chunk = &manifest_new.chunk_table[chunk_index];
+            let fetch = if diff.fetch_chunks.contains(&chunk_index) {
+                " (fetch)"
+            } else {
+                ""
+            };
+            match old_chunks.get(&new_chunk.offset) {
+                None => writeln!(
+                    report,
+                    "    + offset={} size={} hash={}{}",
+                    new_chunk.offset,
+                    new_chunk.size_bytes,
+                    short_hash(&new_chunk.hash),
+                    fetch
+                )
+                .unwrap(),
+                Some(old_chunk)
+                    if old_chunk.size_bytes != new_chunk.size_bytes
+                        || old_chunk.hash != new_chunk.hash =>
+                {
+                    writeln!(
+                        report,
+                        "    ~ offset={} size {} -> {} hash {} -> {}{}",
+                        new_chunk.offset,
+                        old_chunk.size_bytes,
+                        new_chunk.size_bytes,
+                        short_hash(&old_chunk.hash),
+                        short_hash(&new_chunk.hash),
+                        fetch
+                    )
+                    .unwrap()
+                }
+                Some(_) => (),
+            }
+        }
+        let new_offsets: HashSet<u64> = manifest_new.chunk_table[new_chunk_range]
+            .iter()
+            .map(|c| c.offset)
+            .collect();
+        for (offset, old_chunk) in old_chunks {
+            if !new_offsets.contains(&offset) {
+                writeln!(
+                    report,
+                    "    - offset={} size={} hash={}",
+                    offset,
+                    old_chunk.size_bytes,
+                    short_hash(&old_chunk.hash)
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    for old_file in manifest_old.file_table.iter() {
+        if !new_paths.contains(old_file.relative_path.as_path()) {
+            writeln!(report, "- {}", old_file.to_compact_string()).unwrap();
+        }
+    }
+
+    report
+}
+
+/// Returns a manifest with the same files and chunks as `manifest`, with the
+/// file table sorted by relative path and the chunk table sorted by file index
+/// and offset, as produced by `compute_manifest`.
+///
+/// File indices in the chunk table are remapped accordingly and all file
+/// hashes are recomputed according to the manifest version. If the input was
+/// not canonically ordered (see `ManifestData::is_canonically_ordered`), the
+/// manifest hash of the result may therefore differ from that of the input.
+pub fn canonicalize_manifest(manifest: &Manifest) -> Manifest {
+    let mut file_order: Vec<usize> = (0..manifest.file_table.len()).collect();
+    file_order.sort_by(|a, b| {
+        manifest.file_table[*a]
+            .relative_path
+            .cmp(&manifest.file_table[*b].relative_path)
+    });
+    let mut new_file_index = vec![0u32; file_order.len()];
+    for (new_index, old_index) in file_order.iter().enumerate() {
+        new_file_index[*old_index] = new_index as u32;
+    }
+
+    let mut chunk_table: Vec<ChunkInfo> = manifest
+        .chunk_table
+        .iter()
+        .map(|chunk_info| ChunkInfo {
+            file_index: new_file_index[chunk_info.file_index as usize],
+            ..chunk_info.clone()
+        })
+        .collect();
+    chunk_table.sort_by_key(|chunk_info| (chunk_info.file_index, chunk_info.offset));
+
+    let file_table = file_order
+        .iter()
+        .enumerate()
+        .map(|(new_index, old_index)| {
+            let file_info = &manifest.file_table[*old_index];
+            FileInfo {
+                relative_path: file_info.relative_path.clone(),
+                size_bytes: file_info.size_bytes,
+                hash: file_hash(
+                    &chunk_table[file_chunk_range(&chunk_table, new_index)],
+                    manifest.version,
+                ),
+            }
+        })
+        .collect();
+
+    Manifest::new(manifest.version, file_table, chunk_table)
+}
+
+/// Merges partial manifests, each covering a disjoint set of files, into a
+/// single manifest of the requested `version`.
+///
+/// The file tables are concatenated and the file indices in the chunk tables
+/// are remapped to point into the combined file table. The result is then
+/// brought into canonical order and its file hashes are recomputed, see
+/// `canonicalize_manifest`, so it is the same as the manifest computed from
+/// scratch for all the files. Returns an error if a file path occurs in more
+/// than one part.
+pub fn merge_manifests(
+    parts: &[Manifest],
+    version: StateSyncVersion,
+) -> Result<Manifest, MergeError> {
+    let mut paths = HashSet::new();
+    let mut file_table = Vec::new();
+    let mut chunk_table = Vec::new();
+    for part in parts {
+        let file_index_offset = file_table.len() as u32;
+        for file_info in part.file_table.iter() {
+            if !paths.insert(file_info.relative_path.as_path()) {
+                return Err(MergeError::DuplicatePath {
+                    relative_path: file_info.relative_path.clone(),
+                });
+            }
+            file_table.push(file_info.clone());
+        }
+        chunk_table.extend(part.chunk_table.iter().map(|chunk_info| ChunkInfo {
+            file_index: chunk_info.file_index + file_index_offset,
+            ..chunk_info.clone()
+        }));
+    }
+    Ok(canonicalize_manifest(&Manifest::new(
+        version,
+        file_table,
+        chunk_table,
+    )))
+}
+
+/// Builds a manifest that only contains the files at `paths`.
+///
+/// The selected files keep their relative order from `manifest`, their chunks
+/// are re-indexed to point into the compacted file table and the file hashes
+/// are recomputed using the requested `version`. Returns an error if any of
+/// the paths is not in the file table.
+pub fn subset_for_paths(
+    manifest: &Manifest,
+    paths: &[PathBuf],
+    version: StateSyncVersion,
+) -> Result<Manifest, SubsetError> {
+    let mut file_indices = Vec::with_capacity(paths.len());
+    for path in paths {
+        let file_index = manifest
+            .file_table
+            .binary_search_by_key(&path, |file_info| &file_info.relative_path)
+            .map_err(|_| SubsetError::PathNotFound {
+                relative_path: path.clone(),
+            })?;
+        file_indices.push(file_index);
+    }
+    file_indices.sort_unstable();
+    file_indices.dedup();
+
+    let mut file_table = Vec::with_capacity(file_indices.len());
+    let mut chunk_table = Vec::new();
+
+    for (new_file_index, old_file_index) in file_indices.into_iter().enumerate() {
+        let first_chunk = chunk_table.len();
+        chunk_table.extend(
+            manifest.chunk_table[file_chunk_range(&manifest.chunk_table, old_file_index)]
+                .iter()
+                .map(|chunk_info| ChunkInfo {
+                    file_index: new_file_index as u32,
+                    ..chunk_info.clone()
+                }),
+        );
+
+        let file_info = &manifest.file_table[old_file_index];
+        file_table.push(FileInfo {
+            relative_path: file_info.relative_path.clone(),
+            size_bytes: file_info.size_bytes,
+            hash: file_hash(&chunk_table[first_chunk..], version),
+        });
+    }
+
+    Ok(Manifest::new(version, file_table, chunk_table))
+}
+
 /// Filters out all-zero chunks in the manifest chunk table and returns the set
 /// of remaining chunks indices.
 pub fn filter_out_zero_chunks(manifest: &Manifest) -> HashSet<usize> {
@@ -76,3 +76,15 @@ pub fn file_hasher() -> Sha256 {
 pub fn chunk_hasher() -> Sha256 {
     hasher_for_domain("ic-state-chunk")
 }
+
+pub fn merkle_root_hasher() -> Sha256 {
+    hasher_for_domain("ic-state-merkle-root")
+}
+
+pub fn merkle_node_hasher() -> Sha256 {
+    hasher_for_domain("ic-state-merkle-node")
+}
+
+pub fn merkle_leaf_hasher() -> Sha256 {
+    hasher_for_domain("ic-state-merkle-leaf")
+}
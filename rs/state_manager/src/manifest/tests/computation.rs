@@ -1,10 +1,12 @@
 use crate::manifest::{
-    build_file_group_chunks, build_meta_manifest, compute_manifest, diff_manifest,
-    file_chunk_range, filter_out_zero_chunks, hash::ManifestHash, manifest_hash, manifest_hash_v1,
-    manifest_hash_v2, meta_manifest_hash, validate_chunk, validate_manifest,
-    validate_meta_manifest, validate_sub_manifest, ChunkValidationError, DiffScript,
-    ManifestMetrics, ManifestValidationError, StateSyncVersion, DEFAULT_CHUNK_SIZE,
-    MAX_FILE_SIZE_TO_GROUP,
+    build_file_group_chunks, build_meta_manifest, canonicalize_manifest, compute_file_info,
+    compute_manifest, diff_manifest, diff_report, file_chunk_range, filter_out_zero_chunks,
+    hash::ManifestHash, hash_tree_proof, manifest_hash, manifest_hash_v1, manifest_hash_v2,
+    merge_manifests, merkle_proof, merkle_root, meta_manifest_hash, rechunk_file, subset_for_paths,
+    validate_chunk, validate_manifest, validate_meta_manifest, validate_sub_manifest,
+    verify_file_hashes, verify_file_inclusion, verify_merkle_proof, ChunkValidationError,
+    ContentKeyedManifest, DiffScript, ManifestMetrics, ManifestValidationError, MergeError,
+    StateSyncVersion, SubsetError, DEFAULT_CHUNK_SIZE, MAX_FILE_SIZE_TO_GROUP,
 };
 
 use ic_crypto_sha::Sha256;
@@ -16,13 +18,13 @@ use ic_types::{
     crypto::CryptoHash,
     state_sync::{
         decode_manifest, encode_manifest, ChunkInfo, FileGroupChunks, FileInfo, Manifest,
-        FILE_GROUP_CHUNK_ID_OFFSET,
+        ManifestMetadata, FILE_GROUP_CHUNK_ID_OFFSET,
     },
     CryptoHashOfState, Height,
 };
 use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 const NUM_THREADS: u32 = 3;
 
@@ -249,6 +251,49 @@ fn simple_manifest_all_supported_versions() -> Vec<([u8; 32], Manifest)> {
     vec![simple_manifest(), simple_manifest_v2()]
 }
 
+// Files that are empty, smaller than, larger than and exactly as large as a
+// chunk, sorted by path as in the file table.
+fn files_of_various_sizes() -> Vec<(PathBuf, Vec<u8>)> {
+    let chunk_size = DEFAULT_CHUNK_SIZE as usize;
+    vec![
+        (PathBuf::from("a_empty"), vec![]),
+        (PathBuf::from("b_small"), vec![1u8; 1000]),
+        (
+            PathBuf::from("c_large"),
+            (0..2 * chunk_size + 100).map(|i| (i % 251) as u8).collect(),
+        ),
+        (PathBuf::from("d_exact"), vec![3u8; chunk_size]),
+    ]
+}
+
+// Writes the files to a temporary directory and computes the manifest of that
+// directory with `compute_manifest`.
+fn write_files_and_compute_manifest(
+    files: &[(PathBuf, Vec<u8>)],
+    version: StateSyncVersion,
+    chunk_size: u32,
+) -> Manifest {
+    let dir = tempfile::TempDir::new().expect("failed to create a temporary directory");
+    let root = dir.path();
+    for (path, contents) in files {
+        fs::write(root.join(path), contents)
+            .unwrap_or_else(|_| panic!("failed to create file '{}'", path.display()));
+    }
+
+    let metrics_registry = MetricsRegistry::new();
+    let mut thread_pool = scoped_threadpool::Pool::new(NUM_THREADS);
+    compute_manifest(
+        &mut thread_pool,
+        &ManifestMetrics::new(&metrics_registry),
+        &no_op_logger(),
+        version,
+        &CheckpointLayout::new_untracked(root.to_path_buf(), Height::new(0)).unwrap(),
+        chunk_size,
+        None,
+    )
+    .expect("failed to compute manifest")
+}
+
 #[test]
 fn test_simple_manifest_computation() {
     let metrics_registry = MetricsRegistry::new();
@@ -321,6 +366,68 @@ fn test_meta_manifest_computation() {
     assert_eq!(expected_meta_manifest, meta_manifest)
 }
 
+#[test]
+fn test_manifest_metadata_not_hashed() {
+    let metadata = ManifestMetadata {
+        checkpoint_height: 42,
+        label: "test".to_string(),
+    };
+
+    for (expected_hash, manifest) in simple_manifest_all_supported_versions() {
+        let with_metadata = manifest.clone().with_metadata(Some(metadata.clone()));
+        assert_ne!(encode_manifest(&with_metadata), encode_manifest(&manifest));
+        assert_eq!(manifest_hash(&with_metadata), expected_hash);
+
+        let decoded = decode_manifest(&encode_manifest(&with_metadata)).unwrap();
+        assert_eq!(decoded.metadata, Some(metadata.clone()));
+        assert_eq!(manifest_hash(&decoded), expected_hash);
+
+        if manifest.version >= StateSyncVersion::V2 {
+            assert_eq!(
+                build_meta_manifest(&with_metadata),
+                build_meta_manifest(&manifest)
+            );
+            let root_hash = CryptoHashOfState::from(CryptoHash(expected_hash.to_vec()));
+            let proof = hash_tree_proof(&with_metadata, 0).unwrap();
+            assert!(verify_file_inclusion(
+                &root_hash,
+                &proof,
+                &manifest.file_table[0]
+            ));
+        }
+    }
+}
+
+#[test]
+fn test_content_keyed_manifest() {
+    let metadata = ManifestMetadata {
+        checkpoint_height: 42,
+        label: "test".to_string(),
+    };
+
+    for (expected_hash, manifest) in simple_manifest_all_supported_versions() {
+        let keyed = ContentKeyedManifest::new(manifest.clone());
+        assert_eq!(keyed.content_hash(), expected_hash);
+        assert_eq!(keyed.manifest(), &manifest);
+
+        // Metadata makes the manifests unequal, but not their contents.
+        let with_metadata = manifest.clone().with_metadata(Some(metadata.clone()));
+        assert_ne!(with_metadata, manifest);
+        let keyed_with_metadata = ContentKeyedManifest::new(with_metadata);
+        assert_eq!(keyed_with_metadata, keyed);
+
+        let set: HashSet<_> = [keyed.clone(), keyed_with_metadata].into_iter().collect();
+        assert_eq!(set.len(), 1);
+
+        // A different chunk hash changes both.
+        let (version, file_table, mut chunk_table) = manifest.clone().into_parts();
+        chunk_table[0].hash[0] ^= 1;
+        let modified = Manifest::new(version, file_table, chunk_table);
+        assert_ne!(modified, manifest);
+        assert_ne!(ContentKeyedManifest::new(modified), keyed);
+    }
+}
+
 #[test]
 fn test_validate_sub_manifest() {
     let (file_table, chunk_table) = dummy_file_table_and_chunk_table();
@@ -489,6 +596,106 @@ fn bad_file_hash_detected() {
     }
 }
 
+#[test]
+fn zero_length_file_has_no_chunks_and_empty_hash() {
+    let empty_file_hash = hash_concat!(13u8, b"ic-state-file", 0u32);
+    for version in [
+        StateSyncVersion::V1,
+        StateSyncVersion::V2,
+        StateSyncVersion::V3,
+    ] {
+        let (empty, empty_chunks) = compute_file_info(0, Path::new("a_empty"), &[], version);
+        let (small, small_chunks) =
+            compute_file_info(1, Path::new("b_small"), &[1u8; 1000], version);
+        assert!(empty_chunks.is_empty());
+        assert_eq!(empty.size_bytes, 0);
+        assert_eq!(empty.hash, empty_file_hash);
+
+        let manifest = Manifest::new(version, vec![empty, small], small_chunks);
+        assert_eq!(manifest.zero_length_files(), vec![0]);
+        assert!(manifest.coverage_gaps().is_empty());
+        let root_hash = CryptoHashOfState::from(CryptoHash(manifest_hash(&manifest).to_vec()));
+        assert_eq!(validate_manifest(&manifest, &root_hash), Ok(()));
+
+        // Even an empty chunk is rejected for a zero-length file.
+        let mut chunk_table = manifest.chunk_table.clone();
+        chunk_table.insert(
+            0,
+            ChunkInfo {
+                file_index: 0,
+                size_bytes: 0,
+                offset: 0,
+                hash: hash_concat!(14u8, b"ic-state-chunk"),
+            },
+        );
+        let manifest = Manifest::new(version, manifest.file_table.clone(), chunk_table);
+        assert!(manifest.coverage_gaps().is_empty());
+        assert_eq!(
+            validate_manifest(&manifest, &root_hash),
+            Err(ManifestValidationError::InvalidZeroLengthFile {
+                relative_path: PathBuf::from("a_empty"),
+                num_chunks: 1,
+            })
+        );
+    }
+}
+
+#[test]
+fn validate_manifest_rejects_duplicate_paths() {
+    for version in [
+        StateSyncVersion::V1,
+        StateSyncVersion::V2,
+        StateSyncVersion::V3,
+    ] {
+        let (a, a_chunks) = compute_file_info(0, Path::new("a"), &[1u8; 100], version);
+        let (b, b_chunks) = compute_file_info(1, Path::new("b"), &[2u8; 100], version);
+        let manifest = Manifest::new(
+            version,
+            vec![a.clone(), b],
+            [a_chunks.clone(), b_chunks].concat(),
+        );
+        assert!(manifest.find_duplicate_paths().is_empty());
+        let root_hash = CryptoHashOfState::from(CryptoHash(manifest_hash(&manifest).to_vec()));
+        assert_eq!(validate_manifest(&manifest, &root_hash), Ok(()));
+
+        // The second file has the same contents and path as the first one, so
+        // its file hash is valid, but the manifest is still rejected.
+        let (a_again, a_again_chunks) = compute_file_info(1, Path::new("a/"), &[1u8; 100], version);
+        let manifest = Manifest::new(
+            version,
+            vec![a, a_again],
+            [a_chunks, a_again_chunks].concat(),
+        );
+        assert_eq!(manifest.find_duplicate_paths(), vec![PathBuf::from("a")]);
+        let root_hash = CryptoHashOfState::from(CryptoHash(manifest_hash(&manifest).to_vec()));
+        assert_eq!(
+            validate_manifest(&manifest, &root_hash),
+            Err(ManifestValidationError::DuplicatePath {
+                relative_path: PathBuf::from("a"),
+            })
+        );
+    }
+}
+
+#[test]
+fn test_verify_file_hashes() {
+    for (_, manifest) in simple_manifest_all_supported_versions() {
+        assert_eq!(verify_file_hashes(&manifest), Ok(()));
+
+        let decoded = decode_manifest(&encode_manifest(&manifest)[..]).unwrap();
+        assert_eq!(verify_file_hashes(&decoded), Ok(()));
+
+        let mut file_table = manifest.file_table.to_owned();
+        file_table[2].hash[0] ^= 1;
+        let tampered = Manifest::new(
+            manifest.version,
+            file_table,
+            manifest.chunk_table.to_owned(),
+        );
+        assert_eq!(verify_file_hashes(&tampered), Err(vec![2]));
+    }
+}
+
 #[test]
 fn bad_chunk_size_detected() {
     for (_, manifest) in simple_manifest_all_supported_versions() {
@@ -633,6 +840,160 @@ fn test_diff_simple_manifest() {
     );
 }
 
+#[test]
+fn test_canonicalize_manifest() {
+    for (expected_hash, manifest) in simple_manifest_all_supported_versions() {
+        assert!(manifest.is_canonically_ordered());
+        assert_eq!(canonicalize_manifest(&manifest), manifest);
+
+        // Reverse the file table and interleave the chunks of different files.
+        let num_files = manifest.file_table.len() as u32;
+        let file_table: Vec<FileInfo> = manifest.file_table.iter().rev().cloned().collect();
+        let mut chunk_table: Vec<ChunkInfo> = manifest
+            .chunk_table
+            .iter()
+            .map(|chunk_info| ChunkInfo {
+                file_index: num_files - 1 - chunk_info.file_index,
+                ..chunk_info.clone()
+            })
+            .collect();
+        chunk_table.reverse();
+        let shuffled = Manifest::new(manifest.version, file_table, chunk_table);
+        assert!(!shuffled.is_canonically_ordered());
+
+        let canonical = canonicalize_manifest(&shuffled);
+        assert!(canonical.is_canonically_ordered());
+        assert_eq!(canonical, manifest);
+        assert_eq!(manifest_hash(&canonical), expected_hash);
+    }
+}
+
+#[test]
+fn test_merge_manifests() {
+    let mut files = files_of_various_sizes();
+    files.push((PathBuf::from("e_small"), vec![1u8; 1000]));
+
+    for version in [
+        StateSyncVersion::V1,
+        StateSyncVersion::V2,
+        StateSyncVersion::V3,
+    ] {
+        // Each part is computed independently, with its own file indices.
+        let part = |file_indices: &[usize]| {
+            let mut file_table = Vec::new();
+            let mut chunk_table = Vec::new();
+            for (part_index, file_index) in file_indices.iter().enumerate() {
+                let (path, contents) = &files[*file_index];
+                let (file_info, chunks) =
+                    compute_file_info(part_index as u32, path, contents, version);
+                file_table.push(file_info);
+                chunk_table.extend(chunks);
+            }
+            Manifest::new(version, file_table, chunk_table)
+        };
+        let expected = part(&[0, 1, 2, 3, 4]);
+
+        for parts in [
+            vec![part(&[0, 1]), part(&[2, 3, 4])],
+            vec![part(&[3, 1]), part(&[4, 0, 2])],
+            vec![part(&[2]), part(&[4, 0]), part(&[1, 3])],
+            vec![part(&[]), part(&[0, 1, 2, 3, 4])],
+        ] {
+            let merged = merge_manifests(&parts, version).unwrap();
+            assert_eq!(merged, expected);
+            assert_eq!(manifest_hash(&merged), manifest_hash(&expected));
+        }
+
+        assert_eq!(
+            merge_manifests(&[part(&[0, 2]), part(&[1]), part(&[3, 2])], version),
+            Err(MergeError::DuplicatePath {
+                relative_path: PathBuf::from("c_large")
+            })
+        );
+    }
+
+    assert_eq!(
+        merge_manifests(&[], StateSyncVersion::V3).unwrap(),
+        Manifest::new(StateSyncVersion::V3, vec![], vec![])
+    );
+}
+
+#[test]
+fn test_diff_report() {
+    let file = |path: &str, size_bytes: u64, hash: u8| FileInfo {
+        relative_path: path.into(),
+        size_bytes,
+        hash: [hash; 32],
+    };
+    let chunk = |file_index: u32, offset: u64, hash: u8| ChunkInfo {
+        file_index,
+        size_bytes: 1024,
+        offset,
+        hash: [hash; 32],
+    };
+
+    let manifest_old = Manifest::new(
+        StateSyncVersion::V3,
+        vec![file("a", 2048, 1), file("b", 1024, 2)],
+        vec![chunk(0, 0, 10), chunk(0, 1024, 11), chunk(1, 0, 12)],
+    );
+    assert_eq!(diff_report(&manifest_old, &manifest_old), "");
+
+    // Add a file.
+    let manifest_new = Manifest::new(
+        StateSyncVersion::V3,
+        vec![file("a", 2048, 1), file("b", 1024, 2), file("c", 1024, 3)],
+        vec![
+            chunk(0, 0, 10),
+            chunk(0, 1024, 11),
+            chunk(1, 0, 12),
+            chunk(2, 0, 13),
+        ],
+    );
+    assert_eq!(
+        diff_report(&manifest_old, &manifest_new),
+        format!("+ c size=1024 hash={}\n", hex::encode([3u8; 8]))
+    );
+
+    // Remove a file.
+    let manifest_new = Manifest::new(
+        StateSyncVersion::V3,
+        vec![file("a", 2048, 1)],
+        vec![chunk(0, 0, 10), chunk(0, 1024, 11)],
+    );
+    assert_eq!(
+        diff_report(&manifest_old, &manifest_new),
+        format!("- b size=1024 hash={}\n", hex::encode([2u8; 8]))
+    );
+
+    // Change a chunk of a file.
+    let manifest_new = Manifest::new(
+        StateSyncVersion::V3,
+        vec![file("a", 2048, 4), file("b", 1024, 2)],
+        vec![chunk(0, 0, 10), chunk(0, 1024, 14), chunk(1, 0, 12)],
+    );
+    assert_eq!(
+        diff_report(&manifest_old, &manifest_new),
+        format!(
+            "~ a size 2048 -> 2048 hash {} -> {}\n    ~ offset=1024 size 1024 -> 1024 hash {} -> {} (fetch)\n",
+            hex::encode([1u8; 8]),
+            hex::encode([4u8; 8]),
+            hex::encode([11u8; 8]),
+            hex::encode([14u8; 8]),
+        )
+    );
+
+    // A changed chunk whose content exists in the old state is not fetched.
+    let manifest_new = Manifest::new(
+        StateSyncVersion::V3,
+        vec![file("a", 2048, 4), file("b", 1024, 2)],
+        vec![chunk(0, 0, 10), chunk(0, 1024, 12), chunk(1, 0, 12)],
+    );
+    let report = diff_report(&manifest_old, &manifest_new);
+    assert!(report.contains("    ~ offset=1024 "));
+    assert!(!report.contains("(fetch)"));
+}
+
 #[test]
 fn test_diff_manifest() {
     let metrics_registry = MetricsRegistry::new();
@@ -1185,3 +1546,450 @@ fn test_file_index_independent_file_hash() {
     assert_eq!(file1_hash_v3_before, file1_hash_v3_after);
     assert_eq!(file3_hash_v3_before, file3_hash_v3_after);
 }
+
+#[test]
+fn test_compute_file_info() {
+    // An empty file has no chunks.
+    let (file_info, chunks) = compute_file_info(0, Path::new("empty"), &[], StateSyncVersion::V2);
+    assert!(chunks.is_empty());
+    assert_eq!(file_info.size_bytes, 0);
+    assert_eq!(file_info.hash, hash_concat!(13u8, b"ic-state-file", 0u32));
+
+    // A file smaller than a chunk has exactly one chunk covering all of it.
+    let contents = vec![7u8; 1000];
+    let (file_info, chunks) =
+        compute_file_info(3, Path::new("small"), &contents, StateSyncVersion::V2);
+    let chunk_hash = hash_concat!(14u8, b"ic-state-chunk", contents.as_slice());
+    assert_eq!(
+        chunks,
+        vec![ChunkInfo {
+            file_index: 3,
+            size_bytes: 1000,
+            offset: 0,
+            hash: chunk_hash,
+        }]
+    );
+    assert_eq!(file_info.relative_path, PathBuf::from("small"));
+    assert_eq!(file_info.size_bytes, 1000);
+    assert_eq!(
+        file_info.hash,
+        hash_concat!(
+            13u8,
+            b"ic-state-file",
+            1u32,
+            3u32,
+            1000u32,
+            0u64,
+            &chunk_hash[..]
+        )
+    );
+
+    // A file spanning several chunks is split at `DEFAULT_CHUNK_SIZE`.
+    let chunk_size = DEFAULT_CHUNK_SIZE as usize;
+    let contents: Vec<u8> = (0..2 * chunk_size + 100).map(|i| (i % 251) as u8).collect();
+    let (file_info, chunks) =
+        compute_file_info(1, Path::new("large"), &contents, StateSyncVersion::V3);
+    assert_eq!(file_info.size_bytes, contents.len() as u64);
+    assert_eq!(
+        chunks
+            .iter()
+            .map(|c| (c.offset, c.size_bytes))
+            .collect::<Vec<_>>(),
+        vec![
+            (0, DEFAULT_CHUNK_SIZE),
+            (DEFAULT_CHUNK_SIZE as u64, DEFAULT_CHUNK_SIZE),
+            (2 * DEFAULT_CHUNK_SIZE as u64, 100)
+        ]
+    );
+    for chunk in &chunks {
+        let start = chunk.offset as usize;
+        let end = start + chunk.size_bytes as usize;
+        assert_eq!(
+            chunk.hash,
+            hash_concat!(14u8, b"ic-state-chunk", &contents[start..end])
+        );
+    }
+
+    // Starting with `V3` the file hash does not depend on the file index.
+    let (other_index, _) =
+        compute_file_info(5, Path::new("large"), &contents, StateSyncVersion::V3);
+    assert_eq!(file_info.hash, other_index.hash);
+    let (v2_index_1, _) = compute_file_info(1, Path::new("large"), &contents, StateSyncVersion::V2);
+    let (v2_index_5, _) = compute_file_info(5, Path::new("large"), &contents, StateSyncVersion::V2);
+    assert_ne!(v2_index_1.hash, v2_index_5.hash);
+}
+
+#[test]
+fn test_compute_file_info_matches_compute_manifest() {
+    let files = files_of_various_sizes();
+
+    for version in [
+        StateSyncVersion::V1,
+        StateSyncVersion::V2,
+        StateSyncVersion::V3,
+    ] {
+        let expected = write_files_and_compute_manifest(&files, version, DEFAULT_CHUNK_SIZE);
+
+        let mut file_table = Vec::new();
+        let mut chunk_table = Vec::new();
+        for (file_index, (path, contents)) in files.iter().enumerate() {
+            let (file_info, chunks) = compute_file_info(file_index as u32, path, contents, version);
+            file_table.push(file_info);
+            chunk_table.extend(chunks);
+        }
+        let composed = Manifest::new(version, file_table, chunk_table);
+
+        assert_eq!(composed, expected);
+        assert_eq!(manifest_hash(&composed), manifest_hash(&expected));
+    }
+}
+
+#[test]
+fn test_rechunk_file() {
+    const NEW_CHUNK_SIZE: u32 = DEFAULT_CHUNK_SIZE / 2;
+    let contents: Vec<u8> = (0..3 * DEFAULT_CHUNK_SIZE as usize + 100)
+        .map(|i| (i % 251) as u8)
+        .collect();
+
+    for version in [StateSyncVersion::V2, StateSyncVersion::V3] {
+        let (file_info, chunks) = compute_file_info(2, Path::new("file"), &contents, version);
+        assert_eq!(chunks.len(), 4);
+
+        let (new_file_info, new_chunks) =
+            rechunk_file(2, &file_info, &contents, NEW_CHUNK_SIZE, version);
+        assert_eq!(new_file_info.relative_path, file_info.relative_path);
+        assert_eq!(new_file_info.size_bytes, file_info.size_bytes);
+        assert_ne!(new_file_info.hash, file_info.hash);
+        assert_eq!(new_chunks.len(), 7);
+
+        // The new chunks cover the file without gaps or overlaps.
+        let mut offset = 0;
+        for chunk in &new_chunks {
+            assert_eq!(chunk.file_index, 2);
+            assert_eq!(chunk.offset, offset);
+            assert!(chunk.size_bytes <= NEW_CHUNK_SIZE);
+            let end = offset as usize + chunk.size_bytes as usize;
+            assert_eq!(
+                chunk.hash,
+                hash_concat!(14u8, b"ic-state-chunk", &contents[offset as usize..end])
+            );
+            offset = end as u64;
+        }
+        assert_eq!(offset, file_info.size_bytes);
+        assert_eq!(new_chunks.last().unwrap().size_bytes, 100);
+
+        // Re-chunking back to the original size restores the original entries.
+        assert_eq!(
+            rechunk_file(2, &new_file_info, &contents, DEFAULT_CHUNK_SIZE, version),
+            (file_info, chunks)
+        );
+    }
+}
+
+#[test]
+fn test_rechunk_file_matches_compute_manifest() {
+    const NEW_CHUNK_SIZE: u32 = DEFAULT_CHUNK_SIZE / 2;
+    let files = files_of_various_sizes();
+
+    for version in [StateSyncVersion::V2, StateSyncVersion::V3] {
+        let expected = write_files_and_compute_manifest(&files, version, NEW_CHUNK_SIZE);
+
+        let mut file_table = Vec::new();
+        let mut chunk_table = Vec::new();
+        for (file_index, (path, contents)) in files.iter().enumerate() {
+            let (file_info, _) = compute_file_info(file_index as u32, path, contents, version);
+            let (file_info, chunks) = rechunk_file(
+                file_index as u32,
+                &file_info,
+                contents,
+                NEW_CHUNK_SIZE,
+                version,
+            );
+            file_table.push(file_info);
+            chunk_table.extend(chunks);
+        }
+        let rechunked = Manifest::new(version, file_table, chunk_table);
+
+        assert_eq!(rechunked, expected);
+        assert_eq!(manifest_hash(&rechunked), manifest_hash(&expected));
+    }
+}
+
+#[test]
+fn test_subset_for_paths() {
+    let (file_table, chunk_table) = simple_file_table_and_chunk_table();
+    let manifest = Manifest::new(StateSyncVersion::V1, file_table, chunk_table);
+
+    let validate_subset = |subset: &Manifest| {
+        assert_eq!(
+            Ok(()),
+            validate_manifest(
+                subset,
+                &CryptoHashOfState::from(CryptoHash(manifest_hash(subset).to_vec()))
+            )
+        );
+    };
+
+    // Extracting the first file keeps its hash because its file index does not change.
+    let subset = subset_for_paths(&manifest, &["root.bin".into()], StateSyncVersion::V1).unwrap();
+    assert_eq!(subset.version, StateSyncVersion::V1);
+    assert_eq!(subset.file_table, manifest.file_table[0..1].to_vec());
+    assert_eq!(subset.chunk_table, manifest.chunk_table[0..1].to_vec());
+    validate_subset(&subset);
+
+    // The requested order and duplicates do not matter.
+    for version in [StateSyncVersion::V2, StateSyncVersion::V3] {
+        let subset = subset_for_paths(
+            &manifest,
+            &[
+                "subdir/queue".into(),
+                "subdir/metadata".into(),
+                "subdir/memory".into(),
+                "subdir/metadata".into(),
+            ],
+            version,
+        )
+        .unwrap();
+        assert_eq!(subset.version, version);
+        assert_eq!(
+            subset
+                .file_table
+                .iter()
+                .map(|f| f.relative_path.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                PathBuf::from("subdir/memory"),
+                PathBuf::from("subdir/metadata"),
+                PathBuf::from("subdir/queue")
+            ]
+        );
+        assert_eq!(
+            subset
+                .chunk_table
+                .iter()
+                .map(|c| (c.file_index, c.hash))
+                .collect::<Vec<_>>(),
+            manifest.chunk_table[1..]
+                .iter()
+                .map(|c| (c.file_index - 1, c.hash))
+                .collect::<Vec<_>>()
+        );
+        validate_subset(&subset);
+    }
+
+    assert_eq!(
+        subset_for_paths(
+            &manifest,
+            &["root.bin".into(), "subdir/missing".into()],
+            StateSyncVersion::V2
+        ),
+        Err(SubsetError::PathNotFound {
+            relative_path: "subdir/missing".into()
+        })
+    );
+}
+
+#[test]
+fn test_file_inclusion_proof() {
+    let (expected_hash, manifest) = simple_manifest_v2();
+    let root_hash = CryptoHashOfState::from(CryptoHash(expected_hash.to_vec()));
+
+    for (file_index, file_info) in manifest.file_table.iter().enumerate() {
+        let proof = hash_tree_proof(&manifest, file_index).unwrap();
+        assert_eq!(proof.file_index, file_index);
+        assert!(verify_file_inclusion(&root_hash, &proof, file_info));
+
+        let mut tampered = file_info.clone();
+        tampered.size_bytes += 1;
+        assert!(!verify_file_inclusion(&root_hash, &proof, &tampered));
+
+        let mut tampered = file_info.clone();
+        tampered.hash[0] ^= 1;
+        assert!(!verify_file_inclusion(&root_hash, &proof, &tampered));
+
+        let mut tampered = file_info.clone();
+        tampered.relative_path = "subdir/other".into();
+        assert!(!verify_file_inclusion(&root_hash, &proof, &tampered));
+
+        let mut tampered_proof = proof.clone();
+        tampered_proof.sub_manifest_bytes[0] ^= 1;
+        assert!(!verify_file_inclusion(
+            &root_hash,
+            &tampered_proof,
+            file_info
+        ));
+
+        let mut tampered_proof = proof.clone();
+        tampered_proof.file_index += 1;
+        assert!(!verify_file_inclusion(
+            &root_hash,
+            &tampered_proof,
+            file_info
+        ));
+
+        let bogus_hash = CryptoHashOfState::from(CryptoHash(vec![1u8; 32]));
+        assert!(!verify_file_inclusion(&bogus_hash, &proof, file_info));
+    }
+
+    // A proof for one file does not prove the inclusion of another one.
+    let proof = hash_tree_proof(&manifest, 0).unwrap();
+    assert!(!verify_file_inclusion(
+        &root_hash,
+        &proof,
+        &manifest.file_table[1]
+    ));
+
+    assert_eq!(hash_tree_proof(&manifest, manifest.file_table.len()), None);
+
+    let (_, manifest_v1) = simple_manifest();
+    assert_eq!(hash_tree_proof(&manifest_v1, 0), None);
+}
+
+#[test]
+fn test_file_inclusion_proof_across_sub_manifests() {
+    let num_files = 30_000;
+    let file_table: Vec<FileInfo> = (0..num_files)
+        .map(|i| FileInfo {
+            relative_path: format!("canister_states/{:016}/canister.pbuf", i).into(),
+            size_bytes: 0,
+            hash: [i as u8; 32],
+        })
+        .collect();
+    let manifest = Manifest::new(StateSyncVersion::V2, file_table, vec![]);
+    assert!(encode_manifest(&manifest).len() > DEFAULT_CHUNK_SIZE as usize);
+    let root_hash = CryptoHashOfState::from(CryptoHash(manifest_hash(&manifest).to_vec()));
+
+    // Find the first file whose entry ends in the second sub-manifest.
+    let in_first_sub_manifest = |file_index| {
+        let proof = hash_tree_proof(&manifest, file_index).unwrap();
+        proof.sub_manifest_bytes.len() <= DEFAULT_CHUNK_SIZE as usize
+    };
+    let (mut low, mut high) = (0, num_files - 1);
+    while low < high {
+        let mid = (low + high) / 2;
+        if in_first_sub_manifest(mid) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+
+    for file_index in [0, low - 1, low, num_files - 1] {
+        let proof = hash_tree_proof(&manifest, file_index).unwrap();
+        assert!(verify_file_inclusion(
+            &root_hash,
+            &proof,
+            &manifest.file_table[file_index]
+        ));
+    }
+    assert!(in_first_sub_manifest(low - 1));
+    assert!(!in_first_sub_manifest(low));
+}
+
+#[test]
+fn test_file_inclusion_proof_rejects_non_entry_offset() {
+    // The hash of the first file starts like the encoding of an entry with an
+    // empty path and zero size, so the 36 bytes from there, running into the
+    // second entry, parse as a file table entry that is not in the manifest.
+    let mut hash = [7u8; 32];
+    hash[..4].copy_from_slice(&[0x12, 0x22, 0x1a, 0x20]);
+    let file_table = vec![
+        FileInfo {
+            relative_path: "a".into(),
+            size_bytes: 1,
+            hash,
+        },
+        FileInfo {
+            relative_path: "b".into(),
+            size_bytes: 1,
+            hash: [8u8; 32],
+        },
+    ];
+    let manifest = Manifest::new(StateSyncVersion::V2, file_table, vec![]);
+    let root_hash = CryptoHashOfState::from(CryptoHash(manifest_hash(&manifest).to_vec()));
+
+    let proof = hash_tree_proof(&manifest, 1).unwrap();
+    let hash_offset = proof
+        .sub_manifest_bytes
+        .windows(hash.len())
+        .position(|window| window == hash)
+        .unwrap();
+    let forged = FileInfo {
+        relative_path: "".into(),
+        size_bytes: 0,
+        hash: proof.sub_manifest_bytes[hash_offset + 4..hash_offset + 36]
+            .try_into()
+            .unwrap(),
+    };
+
+    for file_index in 0..2 {
+        let mut forged_proof = proof.clone();
+        forged_proof.file_index = file_index;
+        forged_proof.offset = hash_offset;
+        assert!(!verify_file_inclusion(&root_hash, &forged_proof, &forged));
+    }
+}
+
+#[test]
+fn test_merkle_proof() {
+    for num_files in 1..=17usize {
+        let file_table: Vec<FileInfo> = (0..num_files)
+            .map(|i| FileInfo {
+                relative_path: format!("file_{}", i).into(),
+                size_bytes: i as u64,
+                hash: [i as u8; 32],
+            })
+            .collect();
+        let manifest = Manifest::new(StateSyncVersion::V3, file_table, vec![]);
+        let root = merkle_root(&manifest);
+
+        // ceil(log2(num_files))
+        let max_depth = (usize::BITS - (num_files - 1).leading_zeros()) as usize;
+
+        for (file_index, file_info) in manifest.file_table.iter().enumerate() {
+            let proof = merkle_proof(&manifest, file_index).unwrap();
+            assert!(proof.siblings.len() <= max_depth);
+            assert!(verify_merkle_proof(&root, &proof, file_info));
+
+            let mut tampered = file_info.clone();
+            tampered.size_bytes += 1;
+            assert!(!verify_merkle_proof(&root, &proof, &tampered));
+
+            let mut tampered = file_info.clone();
+            tampered.hash[0] ^= 1;
+            assert!(!verify_merkle_proof(&root, &proof, &tampered));
+
+            let mut tampered = file_info.clone();
+            tampered.relative_path = "other".into();
+            assert!(!verify_merkle_proof(&root, &proof, &tampered));
+
+            for i in 0..proof.siblings.len() {
+                let mut tampered_proof = proof.clone();
+                tampered_proof.siblings[i][0] ^= 1;
+                assert!(!verify_merkle_proof(&root, &tampered_proof, file_info));
+            }
+
+            let mut tampered_proof = proof.clone();
+            tampered_proof.file_index = (file_index + 1) % num_files;
+            if num_files > 1 {
+                assert!(!verify_merkle_proof(&root, &tampered_proof, file_info));
+            }
+
+            let mut tampered_proof = proof.clone();
+            tampered_proof.num_files += 1;
+            assert!(!verify_merkle_proof(&root, &tampered_proof, file_info));
+
+            let mut tampered_proof = proof.clone();
+            tampered_proof.version = StateSyncVersion::V2;
+            assert!(!verify_merkle_proof(&root, &tampered_proof, file_info));
+
+            let mut tampered_proof = proof.clone();
+            tampered_proof.siblings.push([0u8; 32]);
+            assert!(!verify_merkle_proof(&root, &tampered_proof, file_info));
+
+            assert!(!verify_merkle_proof(&[1u8; 32], &proof, file_info));
+        }
+
+        assert_eq!(merkle_proof(&manifest, num_files), None);
+    }
+}
@@ -451,8 +451,8 @@ impl ChunkableArtifact for StateSyncMessage {
         {
             use crate::chunkable::ArtifactChunkData;
             use crate::state_sync::{
-                encode_manifest, encode_meta_manifest, state_sync_chunk_type, StateSyncChunk,
-                DEFAULT_CHUNK_SIZE,
+                encode_manifest_without_metadata, encode_meta_manifest, state_sync_chunk_type,
+                StateSyncChunk, DEFAULT_CHUNK_SIZE,
             };
             use std::os::unix::fs::FileExt;
 
@@ -475,7 +475,7 @@ impl ChunkableArtifact for StateSyncMessage {
                 StateSyncChunk::ManifestChunk(index) => {
                     let index = index as usize;
                     if index < self.meta_manifest.sub_manifest_hashes.len() {
-                        let encoded_manifest = encode_manifest(&self.manifest);
+                        let encoded_manifest = encode_manifest_without_metadata(&self.manifest);
                         let start = index * DEFAULT_CHUNK_SIZE as usize;
                         let end = std::cmp::min(
                             start + DEFAULT_CHUNK_SIZE as usize,
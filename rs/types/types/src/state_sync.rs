@@ -47,7 +47,13 @@
 //!                  · chunk_hash
 //! ```
 //!
+//! * A zero-length file has no entries in the chunk table, so its hash is
+//!   that of an empty slice, `hash(dsep("ic-state-file") · 0 as u32)`, for
+//!   every version.
+//!
 //! * The manifest hash is the hash of the protobuf-encoded meta manifest.
+//!   The manifest metadata is not part of the encoding the meta manifest is
+//!   built from.
 //!
 //! * Before `StateSyncVersion::V3` the file hash additionally includes the file
 //!   index within every chunk entry:
@@ -78,14 +84,18 @@
 //! * The `StateSyncVersion::V0` manifest hash is computed by hashing the file
 //!   table only and does not include a version number.
 pub mod proto;
+mod reader;
+
+pub use reader::ManifestReader;
 
 use crate::chunkable::ChunkId;
 use ic_protobuf::{proxy::ProtoProxy, state::sync::v1 as pb};
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap, HashSet},
     fmt::{self, Display},
     ops::{Deref, Range},
+    path::{Path, PathBuf},
     sync::Arc,
 };
 use strum_macros::EnumIter;
@@ -125,6 +135,28 @@ pub const MANIFEST_CHUNK_ID_OFFSET: u32 = 1 << 31;
 #[allow(clippy::assertions_on_constants)]
 const _: () = assert!(MANIFEST_CHUNK_ID_OFFSET > FILE_GROUP_CHUNK_ID_OFFSET);
 
+/// An upper bound on the length of the file table of any plausible manifest.
+//
+// From the analysis for `FILE_GROUP_CHUNK_ID_OFFSET`: at most 10 * 1 << 20
+// canisters with fewer than 10 files each.
+pub const MAX_PLAUSIBLE_FILE_TABLE_LEN: usize = 10 * 10 * (1 << 20);
+
+/// An upper bound on the length of the chunk table of any plausible manifest.
+//
+// From the analysis for `FILE_GROUP_CHUNK_ID_OFFSET`: one chunk per file plus
+// approximately 1 << 20 chunks for each of 100 TiB of state.
+pub const MAX_PLAUSIBLE_CHUNK_TABLE_LEN: usize = MAX_PLAUSIBLE_FILE_TABLE_LEN + 100 * (1 << 20);
+
+/// The IDs of file chunks of a plausible manifest stay below
+/// `FILE_GROUP_CHUNK_ID_OFFSET`, and so do the IDs of file group chunks, of
+/// which there are fewer than files.
+#[allow(clippy::assertions_on_constants)]
+const _: () = assert!(
+    MAX_PLAUSIBLE_CHUNK_TABLE_LEN + FILE_CHUNK_ID_OFFSET <= FILE_GROUP_CHUNK_ID_OFFSET as usize
+);
+#[allow(clippy::assertions_on_constants)]
+const _: () = assert!(MAX_PLAUSIBLE_FILE_TABLE_LEN < FILE_GROUP_CHUNK_ID_OFFSET as usize);
+
 #[derive(
     Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, EnumIter, Serialize, Deserialize,
 )]
@@ -163,6 +195,55 @@ impl Display for StateSyncVersion {
     }
 }
 
+impl StateSyncVersion {
+    /// Returns true if a manifest of version `from` can be migrated to version
+    /// `to` by recomputing its hashes.
+    ///
+    /// Only migrations to the same or to the next version are supported; an
+    /// upgrade across several versions is a sequence of such migrations.
+    /// Downgrades are not supported.
+    pub fn can_migrate(from: Self, to: Self) -> bool {
+        from as u32 == to as u32 || from as u32 + 1 == to as u32
+    }
+
+    /// Returns a short description of what changes when migrating a manifest
+    /// from version `from` to version `to`, or `None` if the migration is not
+    /// supported, see `can_migrate`. See note [Manifest Hash] for the details
+    /// of each version.
+    pub fn migration_note(from: Self, to: Self) -> Option<&'static str> {
+        use StateSyncVersion::*;
+        match (from, to) {
+            _ if from == to => Some("no changes"),
+            (V0, V1) => {
+                Some("the manifest hash additionally covers the version and the chunk table")
+            }
+            (V1, V2) => Some("the manifest hash is the hash of the meta-manifest"),
+            (V2, V3) => Some("the file index is no longer included in file hashes"),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the chunk entries hashed into file hashes include the
+    /// file index, which is the case before `V3`. See note [Manifest Hash].
+    pub fn hashes_include_file_index(&self) -> bool {
+        *self < StateSyncVersion::V3
+    }
+
+    /// Returns all versions up to and including
+    /// `MAX_SUPPORTED_STATE_SYNC_VERSION`, in increasing order.
+    pub fn supported_iter() -> impl Iterator<Item = StateSyncVersion> {
+        use strum::IntoEnumIterator;
+        StateSyncVersion::iter().filter(|version| *version <= MAX_SUPPORTED_STATE_SYNC_VERSION)
+    }
+
+    /// Returns all versions up to and including `CURRENT_STATE_SYNC_VERSION`,
+    /// in increasing order.
+    pub fn current_and_below() -> impl Iterator<Item = StateSyncVersion> {
+        use strum::IntoEnumIterator;
+        StateSyncVersion::iter().filter(|version| *version <= CURRENT_STATE_SYNC_VERSION)
+    }
+}
+
 /// The version of StateSync protocol that should be used for all newly created manifests.
 pub const CURRENT_STATE_SYNC_VERSION: StateSyncVersion = StateSyncVersion::V2;
 
@@ -204,7 +285,81 @@ pub fn state_sync_chunk_type(chunk_id: u32) -> StateSyncChunk {
     }
 }
 
+impl StateSyncChunk {
+    /// Returns the chunk ID of this chunk, or `None` if the index lies outside
+    /// of the ID range of its chunk type. This is the inverse of
+    /// `state_sync_chunk_type`.
+    pub fn chunk_id(&self) -> Option<u32> {
+        let chunk_id = match self {
+            StateSyncChunk::MetaManifestChunk => Some(0),
+            StateSyncChunk::FileChunk(index) => index.checked_add(FILE_CHUNK_ID_OFFSET as u32),
+            StateSyncChunk::FileGroupChunk(chunk_id) => Some(*chunk_id),
+            StateSyncChunk::ManifestChunk(index) => index.checked_add(MANIFEST_CHUNK_ID_OFFSET),
+        }?;
+        (state_sync_chunk_type(chunk_id) == *self).then_some(chunk_id)
+    }
+
+    /// The labels returned by `category_label`, one per chunk type.
+    pub const CATEGORY_LABELS: [&'static str; 4] =
+        ["meta_manifest", "file", "file_group", "manifest"];
+
+    /// Returns a label for the type of this chunk, e.g. to bucket metrics.
+    ///
+    /// The label does not depend on the index of the chunk, so the set of
+    /// labels (see `CATEGORY_LABELS`) is small and stable.
+    pub fn category_label(&self) -> &'static str {
+        match self {
+            StateSyncChunk::MetaManifestChunk => "meta_manifest",
+            StateSyncChunk::FileChunk(_) => "file",
+            StateSyncChunk::FileGroupChunk(_) => "file_group",
+            StateSyncChunk::ManifestChunk(_) => "manifest",
+        }
+    }
+}
+
+/// Renders the chunk as, e.g., `FileChunk(42)` or `MetaManifestChunk`.
+impl Display for StateSyncChunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Parses either the form produced by `Display` or a raw chunk ID.
+impl std::str::FromStr for StateSyncChunk {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Ok(chunk_id) = s.parse::<u32>() {
+            return Ok(state_sync_chunk_type(chunk_id));
+        }
+        if s == "MetaManifestChunk" {
+            return Ok(StateSyncChunk::MetaManifestChunk);
+        }
+
+        let invalid = || format!("invalid state sync chunk: {:?}", s);
+        let (kind, index) = s
+            .strip_suffix(')')
+            .and_then(|s| s.split_once('('))
+            .ok_or_else(invalid)?;
+        let index: u32 = index.parse().map_err(|_| invalid())?;
+        let chunk = match kind {
+            "FileChunk" => StateSyncChunk::FileChunk(index),
+            "FileGroupChunk" => StateSyncChunk::FileGroupChunk(index),
+            "ManifestChunk" => StateSyncChunk::ManifestChunk(index),
+            _ => return Err(invalid()),
+        };
+        match chunk.chunk_id() {
+            Some(_) => Ok(chunk),
+            None => Err(format!("chunk index out of range: {:?}", s)),
+        }
+    }
+}
+
 /// An entry of the file table.
+///
+/// Every file of a checkpoint has an entry, including zero-length files. The
+/// latter have no entries in the chunk table.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct FileInfo {
     /// Path relative to the checkpoint root.
@@ -216,6 +371,23 @@ pub struct FileInfo {
     pub hash: [u8; 32],
 }
 
+/// Number of leading hash bytes shown by the `to_compact_string` methods.
+const COMPACT_HASH_PREFIX_BYTES: usize = 8;
+
+impl FileInfo {
+    /// Returns a single-line description of this entry for log messages,
+    /// showing the hash as a short hex prefix, e.g.
+    /// `subdir/memory size=2048 hash=0a1b2c3d4e5f6071`.
+    pub fn to_compact_string(&self) -> String {
+        format!(
+            "{} size={} hash={}",
+            self.relative_path.display(),
+            self.size_bytes,
+            hex::encode(&self.hash[..COMPACT_HASH_PREFIX_BYTES])
+        )
+    }
+}
+
 /// An entry of the chunk table.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct ChunkInfo {
@@ -235,10 +407,35 @@ impl ChunkInfo {
     pub fn byte_range(&self) -> Range<usize> {
         self.offset as usize..(self.offset as usize + self.size_bytes as usize)
     }
+
+    /// Returns the offset within the file just past the end of this chunk.
+    pub fn end_offset(&self) -> u64 {
+        self.offset + self.size_bytes as u64
+    }
+
+    /// Returns a single-line description of this entry for log messages,
+    /// showing the hash as a short hex prefix, e.g.
+    /// `file=1 offset=1048576 size=1024 hash=0a1b2c3d4e5f6071`.
+    pub fn to_compact_string(&self) -> String {
+        format!(
+            "file={} offset={} size={} hash={}",
+            self.file_index,
+            self.offset,
+            self.size_bytes,
+            hex::encode(&self.hash[..COMPACT_HASH_PREFIX_BYTES])
+        )
+    }
 }
 
 /// We wrap the actual Manifest (ManifestData) in an Arc, in order to
 /// make Manifest both immutable and cheap to copy
+///
+/// The derived `PartialEq`, `Eq` and `Hash` compare the in-memory
+/// representation, including the version and the metadata. They are meant for
+/// in-memory use only and are not stable across releases; in particular they
+/// are not equivalent to comparing root hashes, as the metadata is not
+/// covered by the root hash. See `ContentKeyedManifest` in the state manager
+/// for keying by content.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct Manifest(
     #[serde(serialize_with = "ic_utils::serde_arc::serialize_arc")]
@@ -256,8 +453,139 @@ impl Manifest {
             version,
             file_table,
             chunk_table,
+            metadata: None,
         }))
     }
+
+    /// Returns this manifest with its metadata replaced by `metadata`.
+    ///
+    /// The metadata is not covered by the manifest hash, so this does not
+    /// change the hash of the manifest.
+    pub fn with_metadata(self, metadata: Option<ManifestMetadata>) -> Self {
+        match Arc::try_unwrap(self.0) {
+            Ok(mut data) => {
+                data.metadata = metadata;
+                Self(Arc::new(data))
+            }
+            Err(shared) => Self(Arc::new(ManifestData {
+                version: shared.version,
+                file_table: shared.file_table.clone(),
+                chunk_table: shared.chunk_table.clone(),
+                metadata,
+            })),
+        }
+    }
+
+    /// Creates a manifest from the parts returned by `into_parts`. Same as
+    /// `Manifest::new`.
+    pub fn from_parts(
+        version: StateSyncVersion,
+        file_table: Vec<FileInfo>,
+        chunk_table: Vec<ChunkInfo>,
+    ) -> Self {
+        Self::new(version, file_table, chunk_table)
+    }
+
+    /// Decomposes the manifest into its version, file table and chunk table.
+    /// The metadata is dropped.
+    ///
+    /// If this is the only reference to the manifest data, the tables are
+    /// moved out without copying. Otherwise they are cloned, leaving the
+    /// other references untouched.
+    pub fn into_parts(self) -> (StateSyncVersion, Vec<FileInfo>, Vec<ChunkInfo>) {
+        match Arc::try_unwrap(self.0) {
+            Ok(data) => (data.version, data.file_table, data.chunk_table),
+            Err(shared) => (
+                shared.version,
+                shared.file_table.clone(),
+                shared.chunk_table.clone(),
+            ),
+        }
+    }
+
+    /// Writes the protobuf encoding of the manifest to `w`.
+    ///
+    /// The bytes written are identical to those returned by `encode_manifest`,
+    /// but the table entries are encoded one at a time, so the full encoding
+    /// is never held in memory.
+    pub fn write_to<W: std::io::Write>(&self, w: &mut W) -> std::io::Result<()> {
+        use prost::encoding;
+        use proto::{
+            MANIFEST_CHUNK_TABLE_TAG, MANIFEST_FILE_TABLE_TAG, MANIFEST_METADATA_TAG,
+            MANIFEST_VERSION_TAG,
+        };
+
+        let mut buf = Vec::new();
+        // Like prost, omit the version if it has the default value.
+        let version = self.version as u32;
+        if version != 0 {
+            encoding::uint32::encode(MANIFEST_VERSION_TAG, &version, &mut buf);
+            w.write_all(&buf)?;
+        }
+        for file_info in self.file_table.iter() {
+            buf.clear();
+            let entry = pb::FileInfo::from(file_info.clone());
+            encoding::message::encode(MANIFEST_FILE_TABLE_TAG, &entry, &mut buf);
+            w.write_all(&buf)?;
+        }
+        for chunk_info in self.chunk_table.iter() {
+            buf.clear();
+            let entry = pb::ChunkInfo::from(chunk_info.clone());
+            encoding::message::encode(MANIFEST_CHUNK_TABLE_TAG, &entry, &mut buf);
+            w.write_all(&buf)?;
+        }
+        if let Some(metadata) = &self.metadata {
+            buf.clear();
+            let metadata = pb::ManifestMetadata::from(metadata.clone());
+            encoding::message::encode(MANIFEST_METADATA_TAG, &metadata, &mut buf);
+            w.write_all(&buf)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a manifest written by `write_to` (or `encode_manifest`) from `r`.
+    ///
+    /// The input is read until EOF before being decoded.
+    pub fn read_from<R: std::io::Read>(r: &mut R) -> Result<Manifest, String> {
+        let mut bytes = Vec::new();
+        r.read_to_end(&mut bytes)
+            .map_err(|err| format!("failed to read Manifest: {}", err))?;
+        decode_manifest(&bytes)
+    }
+
+    /// Returns the length of `encode_manifest_without_metadata(self)`, without
+    /// producing the encoding.
+    pub fn encoded_len_without_metadata(&self) -> usize {
+        use proto::{
+            chunk_info_encoded_len, file_info_encoded_len, length_delimited_encoded_len,
+            MANIFEST_CHUNK_TABLE_TAG, MANIFEST_FILE_TABLE_TAG, MANIFEST_VERSION_TAG,
+        };
+
+        let version = self.version as u32;
+        let mut len = 0;
+        if version != 0 {
+            len += prost::encoding::uint32::encoded_len(MANIFEST_VERSION_TAG, &version);
+        }
+        for file_info in self.file_table.iter() {
+            len += length_delimited_encoded_len(
+                MANIFEST_FILE_TABLE_TAG,
+                file_info_encoded_len(file_info),
+            );
+        }
+        for chunk_info in self.chunk_table.iter() {
+            len += length_delimited_encoded_len(
+                MANIFEST_CHUNK_TABLE_TAG,
+                chunk_info_encoded_len(chunk_info),
+            );
+        }
+        len
+    }
+
+    /// Returns true if the encoded manifest is larger than `DEFAULT_CHUNK_SIZE`,
+    /// i.e. if it is split into more than one sub-manifest during state sync.
+    pub fn needs_meta_manifest(&self) -> bool {
+        self.encoded_len_without_metadata() > DEFAULT_CHUNK_SIZE as usize
+    }
 }
 
 impl Deref for Manifest {
@@ -292,6 +620,327 @@ pub struct ManifestData {
     pub version: StateSyncVersion,
     pub file_table: Vec<FileInfo>,
     pub chunk_table: Vec<ChunkInfo>,
+    /// Information for operators that is not covered by the manifest hash.
+    #[serde(default)]
+    pub metadata: Option<ManifestMetadata>,
+}
+
+/// Information about the checkpoint a manifest was computed for.
+///
+/// The metadata is excluded from the manifest hash and from the manifest
+/// chunks sent during state sync, see `encode_manifest_without_metadata`, so
+/// a manifest fetched from a peer never has metadata.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct ManifestMetadata {
+    pub checkpoint_height: u64,
+    /// A free-form label.
+    pub label: String,
+}
+
+/// Maps each chunk hash of a manifest to the indices of the chunk table entries
+/// with that hash, see `ManifestData::chunk_hash_index`.
+///
+/// The index is a snapshot of the chunk table it was built from. Callers that
+/// look up many hashes build it once and keep it for as long as they need it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChunkHashIndex(HashMap<[u8; 32], Vec<usize>>);
+
+impl ChunkHashIndex {
+    /// Returns true if some entry of the chunk table has the given hash.
+    pub fn contains_chunk_hash(&self, hash: &[u8; 32]) -> bool {
+        self.0.contains_key(hash)
+    }
+
+    /// Returns the indices of all chunk table entries with the given hash, in
+    /// increasing order. Chunks with identical contents share a hash, so there
+    /// can be more than one.
+    pub fn chunk_indices_for_hash(&self, hash: &[u8; 32]) -> &[usize] {
+        self.0
+            .get(hash)
+            .map(|indices| indices.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+impl ManifestData {
+    /// Returns the set of distinct chunk hashes in the chunk table.
+    pub fn chunk_hash_set(&self) -> HashSet<[u8; 32]> {
+        self.chunk_table
+            .iter()
+            .map(|chunk_info| chunk_info.hash)
+            .collect()
+    }
+
+    /// Builds an index of the chunk table by chunk hash, for callers that
+    /// look up many hashes.
+    pub fn chunk_hash_index(&self) -> ChunkHashIndex {
+        let mut index: HashMap<[u8; 32], Vec<usize>> = HashMap::new();
+        for (i, chunk_info) in self.chunk_table.iter().enumerate() {
+            index.entry(chunk_info.hash).or_default().push(i);
+        }
+        ChunkHashIndex(index)
+    }
+
+    /// Maps each distinct chunk hash to the file index and byte range of a
+    /// representative chunk with that hash, namely the first one in the chunk
+    /// table.
+    ///
+    /// Chunks with identical contents only need to be stored once, also
+    /// across checkpoints, see `shared_chunks`.
+    pub fn content_index(&self) -> BTreeMap<[u8; 32], (u32, Range<u64>)> {
+        let mut index = BTreeMap::new();
+        for chunk_info in self.chunk_table.iter() {
+            index.entry(chunk_info.hash).or_insert_with(|| {
+                (
+                    chunk_info.file_index,
+                    chunk_info.offset..chunk_info.end_offset(),
+                )
+            });
+        }
+        index
+    }
+
+    /// Estimates the number of bytes to download in order to sync this
+    /// manifest given the manifest `have` of a locally available state.
+    ///
+    /// This is the total size of the chunks whose hash does not occur anywhere
+    /// in the chunk table of `have`, as chunks with identical contents can be
+    /// copied instead of fetched. Chunks missing from `have` are counted once
+    /// per chunk table entry. Unlike a full diff, file paths and offsets are
+    /// not taken into account.
+    pub fn estimated_fetch_bytes(&self, have: &Manifest) -> u64 {
+        let have = have.chunk_hash_set();
+        self.chunk_table
+            .iter()
+            .filter(|chunk_info| !have.contains(&chunk_info.hash))
+            .map(|chunk_info| chunk_info.size_bytes as u64)
+            .sum()
+    }
+
+    /// Lists the hash and relative path of every file in the format of
+    /// `sha256sum`, i.e. one line `<hex hash>  <relative path>` per file, with
+    /// the lines sorted by path.
+    ///
+    /// Note that these are the file hashes of the manifest, which are computed
+    /// over the chunk table entries of a file rather than over its contents, so
+    /// they will not match the output of `sha256sum` for the same files. The
+    /// listing is meant for comparing manifests with external tools.
+    pub fn to_checksums(&self) -> String {
+        let mut files: Vec<&FileInfo> = self.file_table.iter().collect();
+        files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+        let mut checksums = String::new();
+        for file_info in files {
+            checksums.push_str(&format!(
+                "{}  {}\n",
+                hex::encode(file_info.hash),
+                file_info.relative_path.display()
+            ));
+        }
+        checksums
+    }
+
+    /// Returns the indices of the chunk table entries of the file with the
+    /// given index whose byte ranges intersect `range`, in increasing order.
+    ///
+    /// An empty `range` yields no chunks. Bytes of `range` past the end of the
+    /// file are not covered by any chunk, so they are effectively ignored.
+    pub fn chunks_covering(&self, file_index: u32, range: Range<u64>) -> Vec<usize> {
+        if range.is_empty() {
+            return vec![];
+        }
+        let (file_start, file_end) = self.chunk_index_bounds(file_index).unwrap_or_default();
+        let file_chunks = &self.chunk_table[file_start..file_end];
+
+        // Chunks of a file are sorted by offset and do not overlap.
+        let first = file_chunks.partition_point(|c| c.end_offset() <= range.start);
+        let last = file_chunks.partition_point(|c| c.offset < range.end);
+        (file_start + first..file_start + last.max(first)).collect()
+    }
+
+    /// Returns the bounds `[start, end)` of the chunk table entries of the
+    /// file with the given index, or `None` if there is no such file.
+    ///
+    /// Relies on the chunks of each file being contiguous in the chunk table
+    /// and the chunk table being sorted by file index. The range is empty for
+    /// files without chunks, e.g. empty files.
+    pub fn chunk_index_bounds(&self, file_index: u32) -> Option<(usize, usize)> {
+        if file_index as usize >= self.file_table.len() {
+            return None;
+        }
+        let start = self
+            .chunk_table
+            .partition_point(|c| c.file_index < file_index);
+        let end = self
+            .chunk_table
+            .partition_point(|c| c.file_index <= file_index);
+        Some((start, end))
+    }
+
+    /// Returns the number of chunks of the file with the given index, or 0 if
+    /// there is no such file. See `chunk_index_bounds`.
+    pub fn num_chunks_for_file(&self, file_index: u32) -> usize {
+        self.chunk_index_bounds(file_index)
+            .map_or(0, |(start, end)| end - start)
+    }
+
+    /// Returns the relative paths of all files, in file table order.
+    pub fn relative_paths(&self) -> impl Iterator<Item = &Path> {
+        self.file_table
+            .iter()
+            .map(|file_info| file_info.relative_path.as_path())
+    }
+
+    /// Returns the relative paths of all files, sorted lexicographically.
+    pub fn sorted_relative_paths(&self) -> Vec<&Path> {
+        let mut paths: Vec<&Path> = self.relative_paths().collect();
+        paths.sort_unstable();
+        paths
+    }
+
+    /// Returns the relative paths that appear more than once in the file
+    /// table, sorted and listed once each.
+    ///
+    /// Paths are compared component-wise, so `a/b` and `a/b/` are the same
+    /// path, while `a/b` and `A/B` are not.
+    pub fn find_duplicate_paths(&self) -> Vec<PathBuf> {
+        let mut counts: BTreeMap<&Path, usize> = BTreeMap::new();
+        for path in self.relative_paths() {
+            *counts.entry(path).or_default() += 1;
+        }
+        counts
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(path, _)| path.to_path_buf())
+            .collect()
+    }
+
+    /// Returns the indices of the files whose size is at most
+    /// `threshold_bytes`, in increasing order.
+    ///
+    /// Such files are candidates for being grouped with other small files into
+    /// a single chunk during state sync, see `FileGroupChunks`.
+    pub fn small_file_indices(&self, threshold_bytes: u64) -> Vec<u32> {
+        self.file_table
+            .iter()
+            .enumerate()
+            .filter(|(_, file_info)| file_info.size_bytes <= threshold_bytes)
+            .map(|(file_index, _)| file_index as u32)
+            .collect()
+    }
+
+    /// Returns the indices of the zero-length files, in increasing order.
+    ///
+    /// These files have no chunks, so they are created empty instead of being
+    /// fetched during state sync. See note [Manifest Hash] for their hash.
+    pub fn zero_length_files(&self) -> Vec<u32> {
+        self.file_table
+            .iter()
+            .enumerate()
+            .filter(|(_, file_info)| file_info.size_bytes == 0)
+            .map(|(file_index, _)| file_index as u32)
+            .collect()
+    }
+
+    /// Returns true if the file with the given index exists and its size is at
+    /// most `threshold_bytes`. See `small_file_indices`.
+    pub fn is_groupable(&self, file_index: u32, threshold_bytes: u64) -> bool {
+        matches!(
+            self.file_table.get(file_index as usize),
+            Some(file_info) if file_info.size_bytes <= threshold_bytes
+        )
+    }
+
+    /// Returns the indices of the chunks whose hash lies in `[start, end)`,
+    /// comparing hashes as big-endian integers, in increasing order.
+    pub fn chunks_in_hash_range(&self, start: [u8; 32], end: [u8; 32]) -> Vec<usize> {
+        self.chunk_table
+            .iter()
+            .enumerate()
+            .filter(|(_, chunk_info)| start <= chunk_info.hash && chunk_info.hash < end)
+            .map(|(chunk_index, _)| chunk_index)
+            .collect()
+    }
+
+    /// Splits the chunk indices into `shards` groups by chunk hash, such that
+    /// each group covers an equally sized share of the hash space.
+    ///
+    /// Shard `i` receives the chunks whose hash, read as a fraction of the hash
+    /// space, lies in `[i / shards, (i + 1) / shards)`, which is decided by the
+    /// first 8 bytes of the hash. Every chunk index ends up in exactly one
+    /// shard, and each shard is in increasing order. Returns no shards if
+    /// `shards` is 0.
+    pub fn partition_chunks_by_hash(&self, shards: usize) -> Vec<Vec<usize>> {
+        let mut partition = vec![Vec::new(); shards];
+        if shards == 0 {
+            return partition;
+        }
+        for (chunk_index, chunk_info) in self.chunk_table.iter().enumerate() {
+            let mut prefix = [0u8; 8];
+            prefix.copy_from_slice(&chunk_info.hash[..8]);
+            let shard = (u64::from_be_bytes(prefix) as u128 * shards as u128) >> 64;
+            partition[shard as usize].push(chunk_index);
+        }
+        partition
+    }
+
+    /// Returns true if the file table is sorted by relative path and the chunk
+    /// table by file index and offset, without duplicates, which is the order
+    /// in which `compute_manifest` emits them.
+    pub fn is_canonically_ordered(&self) -> bool {
+        self.file_table
+            .windows(2)
+            .all(|w| w[0].relative_path < w[1].relative_path)
+            && self
+                .chunk_table
+                .windows(2)
+                .all(|w| (w[0].file_index, w[0].offset) < (w[1].file_index, w[1].offset))
+    }
+
+    /// Returns the files whose chunks do not add up to their size, as pairs of
+    /// the file index and the number of bytes by which the total size of the
+    /// chunks of the file falls short of, or exceeds, the size of the file.
+    ///
+    /// Returns an empty vector if every file is covered exactly. Chunks with a
+    /// file index outside of the file table are ignored; see
+    /// `validate_manifest` in the state manager for a full consistency check.
+    pub fn coverage_gaps(&self) -> Vec<(u32, u64)> {
+        let mut covered = vec![0u64; self.file_table.len()];
+        for chunk_info in self.chunk_table.iter() {
+            if let Some(bytes) = covered.get_mut(chunk_info.file_index as usize) {
+                *bytes += chunk_info.size_bytes as u64;
+            }
+        }
+        self.file_table
+            .iter()
+            .zip(covered)
+            .enumerate()
+            .filter(|(_, (file_info, covered))| file_info.size_bytes != *covered)
+            .map(|(file_index, (file_info, covered))| {
+                (file_index as u32, file_info.size_bytes.abs_diff(covered))
+            })
+            .collect()
+    }
+
+    /// Returns an estimate of the number of heap bytes held by this manifest.
+    ///
+    /// The estimate accounts for the allocated capacity of the file and chunk
+    /// tables, of the relative paths in the file table and of the metadata
+    /// label. It ignores allocator overhead, so the actual resident size may be
+    /// slightly larger.
+    pub fn approx_heap_size(&self) -> usize {
+        let file_table_size = self.file_table.capacity() * std::mem::size_of::<FileInfo>();
+        let paths_size: usize = self
+            .file_table
+            .iter()
+            .map(|file_info| file_info.relative_path.capacity())
+            .sum();
+        let chunk_table_size = self.chunk_table.capacity() * std::mem::size_of::<ChunkInfo>();
+        let metadata_size = self
+            .metadata
+            .as_ref()
+            .map_or(0, |metadata| metadata.label.capacity());
+        file_table_size + paths_size + chunk_table_size + metadata_size
+    }
 }
 
 /// MetaManifest describes how the manifest is encoded, split and hashed.
@@ -318,6 +967,20 @@ pub struct MetaManifest {
     pub sub_manifest_hashes: Vec<[u8; 32]>,
 }
 
+impl MetaManifest {
+    /// Returns the number of manifest chunks, i.e. the number of sub-manifests.
+    pub fn num_chunks(&self) -> usize {
+        self.sub_manifest_hashes.len()
+    }
+
+    /// Returns the chunk IDs under which the sub-manifests are fetched, in
+    /// order. The ID of the `i`-th sub-manifest is
+    /// `MANIFEST_CHUNK_ID_OFFSET + i`.
+    pub fn chunk_ids(&self) -> impl Iterator<Item = u32> {
+        (0..self.num_chunks() as u32).map(|i| MANIFEST_CHUNK_ID_OFFSET + i)
+    }
+}
+
 impl fmt::Display for Manifest {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fn write_header(f: &mut fmt::Formatter<'_>, spec: &[(&'static str, usize)]) -> fmt::Result {
@@ -398,17 +1061,86 @@ impl fmt::Display for Manifest {
     }
 }
 
+/// Returns the distinct chunk hashes that occur in both manifests, in
+/// increasing order.
+pub fn shared_chunks(a: &Manifest, b: &Manifest) -> Vec<[u8; 32]> {
+    let b = b.chunk_hash_set();
+    let mut shared: Vec<_> = a
+        .chunk_hash_set()
+        .into_iter()
+        .filter(|hash| b.contains(hash))
+        .collect();
+    shared.sort_unstable();
+    shared
+}
+
 /// Serializes the manifest into a byte array.
 pub fn encode_manifest(manifest: &Manifest) -> Vec<u8> {
     pb::Manifest::proxy_encode(manifest.clone()).expect("Failed to serialize manifest.")
 }
 
+/// Serializes the manifest into a byte array, leaving out its metadata.
+///
+/// This is the encoding that the `StateSyncVersion::V2` manifest hash is
+/// computed over and that is split into manifest chunks during state sync.
+pub fn encode_manifest_without_metadata(manifest: &Manifest) -> Vec<u8> {
+    use prost::Message;
+
+    let mut manifest = pb::Manifest::from(manifest.clone());
+    manifest.metadata = None;
+    manifest.encode_to_vec()
+}
+
 /// Deserializes the manifest from a byte array.
+///
+/// This does not check the manifest version against
+/// `MAX_SUPPORTED_STATE_SYNC_VERSION`: a manifest with a version that is
+/// known but not supported by this replica is accepted here and only causes
+/// a panic later on, when it is used. Use `decode_manifest_checked` for
+/// manifests received from untrusted sources.
 pub fn decode_manifest(bytes: &[u8]) -> Result<Manifest, String> {
     pb::Manifest::proxy_decode(bytes)
         .map_err(|err| format!("failed to convert Manifest proto into an object: {}", err))
 }
 
+/// Deserializes the manifest from a byte array and checks that its version
+/// is a known `StateSyncVersion` not exceeding
+/// `MAX_SUPPORTED_STATE_SYNC_VERSION`.
+///
+/// Unlike `decode_manifest`, this rejects manifests with an unsupported
+/// version with a descriptive error instead of relying on a later panic.
+pub fn decode_manifest_checked(bytes: &[u8]) -> Result<Manifest, String> {
+    use prost::Message;
+
+    let manifest = pb::Manifest::decode(bytes)
+        .map_err(|err| format!("failed to decode Manifest proto: {}", err))?;
+    if manifest.version > MAX_SUPPORTED_STATE_SYNC_VERSION as u32 {
+        return Err(format!(
+            "manifest version {} is not supported, maximum supported version {}",
+            manifest.version, MAX_SUPPORTED_STATE_SYNC_VERSION
+        ));
+    }
+    if let Err(version) = StateSyncVersion::try_from(manifest.version) {
+        return Err(format!("manifest version {} is unknown", version));
+    }
+    Manifest::try_from(manifest)
+        .map_err(|err| format!("failed to convert Manifest proto into an object: {}", err))
+}
+
+/// Serializes the manifest into CBOR, for consumption by off-chain tooling.
+///
+/// This is not the encoding used on the wire or for computing the manifest
+/// hash; those always use the protobuf encoding of `encode_manifest`.
+pub fn encode_manifest_cbor(manifest: &Manifest) -> Vec<u8> {
+    serde_cbor::to_vec(manifest).expect("Failed to serialize manifest to CBOR.")
+}
+
+/// Deserializes a manifest produced by `encode_manifest_cbor`.
+pub fn decode_manifest_cbor(bytes: &[u8]) -> Result<Manifest, String> {
+    serde_cbor::from_slice(bytes)
+        .map_err(|err| format!("failed to decode Manifest from CBOR: {}", err))
+}
+
 pub fn encode_meta_manifest(meta_manifest: &MetaManifest) -> Vec<u8> {
     pb::MetaManifest::proxy_encode(meta_manifest.clone())
         .expect("Failed to serialize meta-manifest.")
@@ -423,6 +1155,30 @@ pub fn decode_meta_manifest(bytes: &[u8]) -> Result<MetaManifest, String> {
     })
 }
 
+/// Deserializes the meta-manifest from a byte array and checks that it is
+/// plausible: its version must not exceed `max_version` and it must list at
+/// least one sub-manifest, since even an empty manifest encodes to a
+/// non-empty byte string.
+///
+/// Unlike `decode_meta_manifest`, this rejects meta-manifests that could only
+/// be detected as unusable later on.
+pub fn decode_meta_manifest_checked(
+    bytes: &[u8],
+    max_version: StateSyncVersion,
+) -> Result<MetaManifest, String> {
+    let meta_manifest = decode_meta_manifest(bytes)?;
+    if meta_manifest.version > max_version {
+        return Err(format!(
+            "meta-manifest version {} is not supported, maximum supported version {}",
+            meta_manifest.version, max_version
+        ));
+    }
+    if meta_manifest.sub_manifest_hashes.is_empty() {
+        return Err("meta-manifest does not contain any sub-manifest hashes".to_string());
+    }
+    Ok(meta_manifest)
+}
+
 type P2PChunkId = u32;
 type ManifestChunkTableIndex = u32;
 
@@ -461,12 +1217,883 @@ impl FileGroupChunks {
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
+
+    /// Returns the total number of chunk table indices over all file group chunks.
+    pub fn total_indices(&self) -> usize {
+        self.0.values().map(|indices| indices.len()).sum()
+    }
+
+    /// Returns the number of chunk table indices in the given file group chunk,
+    /// or 0 if the chunk id is unknown.
+    pub fn indices_in(&self, chunk_id: &P2PChunkId) -> usize {
+        self.0.get(chunk_id).map_or(0, |indices| indices.len())
+    }
+}
+
+/// Tracks which entries of the chunk table of a manifest have been fetched
+/// during an incremental state sync.
+///
+/// Fetching a file group chunk fetches all the chunk table entries it is made
+/// of. The meta-manifest and manifest chunks are not part of the progress.
+#[derive(Clone, Debug)]
+pub struct SyncProgress {
+    chunk_sizes: Vec<u32>,
+    fetched: Vec<bool>,
+    file_group_chunks: FileGroupChunks,
+    bytes_fetched: u64,
+    total_bytes: u64,
+    num_fetched: usize,
+}
+
+impl SyncProgress {
+    /// Creates the progress of fetching the chunks of `manifest`, of which none
+    /// have been fetched yet.
+    pub fn new(manifest: &Manifest, file_group_chunks: FileGroupChunks) -> Self {
+        let chunk_sizes: Vec<u32> = manifest
+            .chunk_table
+            .iter()
+            .map(|chunk_info| chunk_info.size_bytes)
+            .collect();
+        let total_bytes = chunk_sizes.iter().map(|size| *size as u64).sum();
+        Self {
+            fetched: vec![false; chunk_sizes.len()],
+            chunk_sizes,
+            file_group_chunks,
+            bytes_fetched: 0,
+            total_bytes,
+            num_fetched: 0,
+        }
+    }
+
+    /// Marks the chunk with the given ID as fetched.
+    ///
+    /// A file group chunk marks all of its chunk table entries as fetched.
+    /// Marking a chunk more than once, or marking a chunk ID that does not
+    /// refer to a file chunk or a known file group chunk, has no effect.
+    pub fn mark_fetched(&mut self, chunk_id: u32) {
+        match state_sync_chunk_type(chunk_id) {
+            StateSyncChunk::FileChunk(chunk_index) => self.mark_index(chunk_index as usize),
+            StateSyncChunk::FileGroupChunk(chunk_id) => {
+                let indices = self
+                    .file_group_chunks
+                    .get(&chunk_id)
+                    .cloned()
+                    .unwrap_or_default();
+                for chunk_index in indices {
+                    self.mark_index(chunk_index as usize);
+                }
+            }
+            StateSyncChunk::MetaManifestChunk | StateSyncChunk::ManifestChunk(_) => {}
+        }
+    }
+
+    fn mark_index(&mut self, chunk_index: usize) {
+        if let Some(fetched) = self.fetched.get_mut(chunk_index) {
+            if !*fetched {
+                *fetched = true;
+                self.bytes_fetched += self.chunk_sizes[chunk_index] as u64;
+                self.num_fetched += 1;
+            }
+        }
+    }
+
+    /// Returns true if all entries of the chunk table have been fetched.
+    pub fn is_complete(&self) -> bool {
+        self.num_fetched == self.fetched.len()
+    }
+
+    /// Returns the total size of the chunk table entries fetched so far.
+    pub fn bytes_fetched(&self) -> u64 {
+        self.bytes_fetched
+    }
+
+    /// Returns the share of the bytes of the chunk table fetched so far, as a
+    /// percentage. A manifest without any bytes to fetch is 100% complete.
+    pub fn percent_complete(&self) -> f64 {
+        if self.total_bytes == 0 {
+            return 100.0;
+        }
+        self.bytes_fetched as f64 * 100.0 / self.total_bytes as f64
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Returns a file table entry with the given path and size and a zero hash.
+    fn file(relative_path: &str, size_bytes: u64) -> FileInfo {
+        FileInfo {
+            relative_path: relative_path.into(),
+            size_bytes,
+            hash: [0; 32],
+        }
+    }
+
+    /// Returns a file table with one file of each of the given sizes, named
+    /// `file_0`, `file_1`, ...
+    fn file_table(sizes: &[u64]) -> Vec<FileInfo> {
+        sizes
+            .iter()
+            .enumerate()
+            .map(|(i, size_bytes)| file(&format!("file_{}", i), *size_bytes))
+            .collect()
+    }
+
+    /// Returns a chunk table entry whose hash consists of `hash` bytes.
+    fn chunk(file_index: u32, offset: u64, size_bytes: u32, hash: u8) -> ChunkInfo {
+        ChunkInfo {
+            file_index,
+            size_bytes,
+            offset,
+            hash: [hash; 32],
+        }
+    }
+
+    /// Returns a manifest of the current version with `num_files` files of
+    /// `chunks_per_file` full chunks each.
+    pub(super) fn manifest_with_files(num_files: u32, chunks_per_file: u32) -> Manifest {
+        versioned_manifest_with_files(CURRENT_STATE_SYNC_VERSION, num_files, chunks_per_file)
+    }
+
+    /// Same as `manifest_with_files`, for the given version.
+    pub(super) fn versioned_manifest_with_files(
+        version: StateSyncVersion,
+        num_files: u32,
+        chunks_per_file: u32,
+    ) -> Manifest {
+        let file_table = (0..num_files)
+            .map(|i| FileInfo {
+                relative_path: format!("canister_states/{:08}/queues.pbuf", i).into(),
+                size_bytes: chunks_per_file as u64 * DEFAULT_CHUNK_SIZE as u64,
+                hash: [1; 32],
+            })
+            .collect();
+        let chunk_table = (0..num_files)
+            .flat_map(|file_index| {
+                (0..chunks_per_file).map(move |i| ChunkInfo {
+                    file_index,
+                    size_bytes: DEFAULT_CHUNK_SIZE,
+                    offset: i as u64 * DEFAULT_CHUNK_SIZE as u64,
+                    hash: [2; 32],
+                })
+            })
+            .collect();
+        Manifest::new(version, file_table, chunk_table)
+    }
+
+    #[test]
+    fn test_manifest_cbor_roundtrip() {
+        use strum::IntoEnumIterator;
+
+        let mut manifests = vec![
+            manifest_with_files(0, 0),
+            manifest_with_files(1, 0),
+            manifest_with_files(1, 1),
+            manifest_with_files(5, 3),
+        ];
+        let base = manifest_with_files(3, 2);
+        for version in StateSyncVersion::iter() {
+            manifests.push(Manifest::new(
+                version,
+                base.file_table.clone(),
+                base.chunk_table.clone(),
+            ));
+        }
+
+        for manifest in manifests {
+            let cbor = encode_manifest_cbor(&manifest);
+            assert_eq!(decode_manifest_cbor(&cbor), Ok(manifest.clone()));
+
+            // The CBOR encoding is a separate format from the protobuf one that
+            // the manifest hash is computed over.
+            let proto = encode_manifest(&manifest);
+            assert_ne!(cbor, proto);
+            assert_ne!(decode_manifest(&cbor).ok(), Some(manifest.clone()));
+            assert_eq!(decode_manifest(&proto), Ok(manifest));
+        }
+    }
+
+    #[test]
+    fn test_manifest_write_to_read_from() {
+        use std::io::Cursor;
+        use strum::IntoEnumIterator;
+
+        let mut manifests = vec![
+            manifest_with_files(0, 0),
+            manifest_with_files(1, 0),
+            manifest_with_files(5, 3),
+        ];
+        let base = manifest_with_files(3, 2);
+        for version in StateSyncVersion::iter() {
+            manifests.push(Manifest::new(
+                version,
+                base.file_table.clone(),
+                base.chunk_table.clone(),
+            ));
+        }
+
+        for manifest in manifests {
+            let mut cursor = Cursor::new(Vec::new());
+            manifest.write_to(&mut cursor).unwrap();
+            assert_eq!(cursor.get_ref(), &encode_manifest(&manifest));
+
+            cursor.set_position(0);
+            assert_eq!(Manifest::read_from(&mut cursor), Ok(manifest));
+        }
+
+        assert!(Manifest::read_from(&mut Cursor::new(vec![0xff; 4])).is_err());
+    }
+
+    #[test]
+    fn test_manifest_metadata_roundtrip() {
+        let manifest = manifest_with_files(3, 2);
+        let encoded_without_metadata = encode_manifest(&manifest);
+        // Manifests encoded before the metadata was introduced decode to `None`.
+        assert_eq!(
+            decode_manifest(&encoded_without_metadata).unwrap().metadata,
+            None
+        );
+
+        let metadata = ManifestMetadata {
+            checkpoint_height: 1_000,
+            label: "nightly".to_string(),
+        };
+        let with_metadata = manifest.clone().with_metadata(Some(metadata.clone()));
+        assert_eq!(with_metadata.metadata, Some(metadata.clone()));
+        assert_eq!(with_metadata.file_table, manifest.file_table);
+        assert_eq!(with_metadata.chunk_table, manifest.chunk_table);
+
+        let encoded = encode_manifest(&with_metadata);
+        assert_ne!(encoded, encoded_without_metadata);
+        assert_eq!(decode_manifest(&encoded), Ok(with_metadata.clone()));
+        assert_eq!(
+            decode_manifest_cbor(&encode_manifest_cbor(&with_metadata)),
+            Ok(with_metadata.clone())
+        );
+
+        let mut written = Vec::new();
+        with_metadata.write_to(&mut written).unwrap();
+        assert_eq!(written, encoded);
+
+        assert_eq!(
+            encode_manifest_without_metadata(&with_metadata),
+            encoded_without_metadata
+        );
+        assert_eq!(with_metadata.with_metadata(None), manifest);
+    }
+
+    #[test]
+    fn test_decode_manifest_cbor_rejects_garbage() {
+        assert!(decode_manifest_cbor(&[]).is_err());
+        assert!(decode_manifest_cbor(&[0xff, 0x00, 0x13]).is_err());
+
+        let cbor = encode_manifest_cbor(&manifest_with_files(2, 2));
+        assert!(decode_manifest_cbor(&cbor[..cbor.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_chunk_hash_lookup() {
+        let manifest = Manifest::new(
+            CURRENT_STATE_SYNC_VERSION,
+            file_table(&[200, 200]),
+            vec![
+                chunk(0, 0, 100, 1),
+                chunk(0, 100, 100, 2),
+                chunk(1, 0, 100, 3),
+                chunk(1, 100, 100, 1),
+            ],
+        );
+
+        assert_eq!(
+            manifest.chunk_hash_set(),
+            maplit::hashset! {[1; 32], [2; 32], [3; 32]}
+        );
+
+        let index = manifest.chunk_hash_index();
+        assert!(index.contains_chunk_hash(&[1; 32]));
+        assert!(index.contains_chunk_hash(&[3; 32]));
+        assert!(!index.contains_chunk_hash(&[4; 32]));
+
+        assert_eq!(index.chunk_indices_for_hash(&[1; 32]), &[0, 3]);
+        assert_eq!(index.chunk_indices_for_hash(&[2; 32]), &[1]);
+        assert!(index.chunk_indices_for_hash(&[4; 32]).is_empty());
+    }
+
+    #[test]
+    fn test_relative_paths() {
+        let paths = [
+            "system_metadata.pbuf",
+            "canister_states/b",
+            "a",
+            "canister_states/a",
+        ];
+        let file_table = paths.iter().map(|path| file(path, 0)).collect();
+        let manifest = Manifest::new(CURRENT_STATE_SYNC_VERSION, file_table, vec![]);
+
+        assert_eq!(
+            manifest.relative_paths().collect::<Vec<_>>(),
+            paths.iter().map(Path::new).collect::<Vec<_>>()
+        );
+
+        let sorted = manifest.sorted_relative_paths();
+        assert_eq!(
+            sorted,
+            vec![
+                Path::new("a"),
+                Path::new("canister_states/a"),
+                Path::new("canister_states/b"),
+                Path::new("system_metadata.pbuf"),
+            ]
+        );
+        assert!(sorted.windows(2).all(|w| w[0] < w[1]));
+
+        assert_eq!(manifest_with_files(0, 0).sorted_relative_paths().len(), 0);
+    }
+
+    #[test]
+    fn test_estimated_fetch_bytes() {
+        let manifest = |hashes: &[u8]| {
+            let chunk_table: Vec<_> = hashes
+                .iter()
+                .enumerate()
+                .map(|(i, hash)| chunk(0, i as u64 * 100, 100, *hash))
+                .collect();
+            let file_table = file_table(&[chunk_table.len() as u64 * 100]);
+            Manifest::new(CURRENT_STATE_SYNC_VERSION, file_table, chunk_table)
+        };
+
+        let target = manifest(&[1, 2, 3, 4]);
+        assert_eq!(target.estimated_fetch_bytes(&target), 0);
+        assert_eq!(target.estimated_fetch_bytes(&manifest(&[4, 3, 2, 1])), 0);
+
+        // Half of the chunks are held, at other positions.
+        assert_eq!(target.estimated_fetch_bytes(&manifest(&[5, 3, 1])), 200);
+
+        let empty = manifest_with_files(0, 0);
+        assert_eq!(target.estimated_fetch_bytes(&empty), 400);
+        assert_eq!(empty.estimated_fetch_bytes(&target), 0);
+
+        // Repeated missing chunks are counted for every entry.
+        assert_eq!(manifest(&[6, 6, 1]).estimated_fetch_bytes(&target), 200);
+    }
+
+    #[test]
+    fn test_zero_length_files() {
+        let manifest = Manifest::new(
+            CURRENT_STATE_SYNC_VERSION,
+            file_table(&[0, 100, 0]),
+            vec![chunk(1, 0, 100, 1)],
+        );
+        assert_eq!(manifest.zero_length_files(), vec![0, 2]);
+        assert!(manifest.coverage_gaps().is_empty());
+        for file_index in [0, 2] {
+            assert_eq!(manifest.num_chunks_for_file(file_index), 0);
+        }
+
+        assert!(manifest_with_files(3, 1).zero_length_files().is_empty());
+        assert_eq!(manifest_with_files(2, 0).zero_length_files(), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_find_duplicate_paths() {
+        let manifest_with_paths = |paths: &[&str]| {
+            Manifest::new(
+                CURRENT_STATE_SYNC_VERSION,
+                paths.iter().map(|path| file(path, 0)).collect(),
+                vec![],
+            )
+        };
+
+        assert!(manifest_with_files(3, 1).find_duplicate_paths().is_empty());
+        assert!(manifest_with_paths(&["a/b", "A/B", "a/B", "a"])
+            .find_duplicate_paths()
+            .is_empty());
+
+        assert_eq!(
+            manifest_with_paths(&["c", "a/b", "c", "d", "c"]).find_duplicate_paths(),
+            vec![PathBuf::from("c")]
+        );
+        assert_eq!(
+            manifest_with_paths(&["a/b/", "x", "a/b", "x/"]).find_duplicate_paths(),
+            vec![PathBuf::from("a/b"), PathBuf::from("x")]
+        );
+    }
+
+    #[test]
+    fn test_state_sync_version_bounded_iterators() {
+        use strum::IntoEnumIterator;
+
+        let supported: Vec<_> = StateSyncVersion::supported_iter().collect();
+        assert_eq!(supported.first(), Some(&StateSyncVersion::V0));
+        assert_eq!(supported.last(), Some(&MAX_SUPPORTED_STATE_SYNC_VERSION));
+
+        let current: Vec<_> = StateSyncVersion::current_and_below().collect();
+        assert_eq!(current.first(), Some(&StateSyncVersion::V0));
+        assert_eq!(current.last(), Some(&CURRENT_STATE_SYNC_VERSION));
+
+        for version in StateSyncVersion::iter() {
+            assert_eq!(
+                supported.contains(&version),
+                version <= MAX_SUPPORTED_STATE_SYNC_VERSION
+            );
+            assert_eq!(
+                current.contains(&version),
+                version <= CURRENT_STATE_SYNC_VERSION
+            );
+        }
+        // Both iterators yield each version once, in increasing order.
+        assert!(supported.windows(2).all(|w| w[0] < w[1]));
+        assert!(current.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_needs_meta_manifest() {
+        let check_len = |manifest: &Manifest| {
+            assert_eq!(
+                manifest.encoded_len_without_metadata(),
+                encode_manifest_without_metadata(manifest).len()
+            );
+        };
+
+        let empty = Manifest::new(CURRENT_STATE_SYNC_VERSION, vec![], vec![]);
+        check_len(&empty);
+        assert!(!empty.needs_meta_manifest());
+
+        // Fields with default values are omitted from the encoding.
+        let defaults = Manifest::new(
+            StateSyncVersion::V0,
+            vec![file("", 0)],
+            vec![chunk(0, 0, 0, 0)],
+        );
+        check_len(&defaults);
+
+        let tiny = manifest_with_files(2, 3).with_metadata(Some(ManifestMetadata {
+            checkpoint_height: 1,
+            label: "tiny".to_string(),
+        }));
+        check_len(&tiny);
+        assert!(!tiny.needs_meta_manifest());
+
+        // Find the smallest number of chunks for which the encoded manifest no
+        // longer fits into a single chunk.
+        let (mut lo, mut hi) = (1, DEFAULT_CHUNK_SIZE);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if manifest_with_files(1, mid).needs_meta_manifest() {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+
+        let below = manifest_with_files(1, lo - 1);
+        check_len(&below);
+        assert!(below.encoded_len_without_metadata() <= DEFAULT_CHUNK_SIZE as usize);
+        assert!(!below.needs_meta_manifest());
+
+        let above = manifest_with_files(1, lo);
+        check_len(&above);
+        assert!(above.encoded_len_without_metadata() > DEFAULT_CHUNK_SIZE as usize);
+        assert!(above.needs_meta_manifest());
+    }
+
+    #[test]
+    fn test_to_checksums() {
+        let file_with_hash = |path, hash| FileInfo {
+            hash: [hash; 32],
+            ..file(path, 0)
+        };
+        let manifest = Manifest::new(
+            CURRENT_STATE_SYNC_VERSION,
+            vec![
+                file_with_hash("system_metadata.pbuf", 3),
+                file_with_hash("a/b.bin", 1),
+                file_with_hash("c", 2),
+            ],
+            vec![],
+        );
+
+        let checksums = manifest.to_checksums();
+        assert!(checksums.ends_with('\n'));
+        let lines: Vec<&str> = checksums.lines().collect();
+        assert_eq!(lines.len(), manifest.file_table.len());
+        assert_eq!(lines[0], format!("{}  a/b.bin", "01".repeat(32)));
+
+        let mut paths = vec![];
+        for line in lines {
+            let (hash, path) = line.split_once("  ").unwrap();
+            let file_info = manifest
+                .file_table
+                .iter()
+                .find(|f| f.relative_path.to_str() == Some(path))
+                .unwrap();
+            assert_eq!(hash, hex::encode(file_info.hash));
+            paths.push(path);
+        }
+        assert_eq!(paths, vec!["a/b.bin", "c", "system_metadata.pbuf"]);
+
+        assert_eq!(manifest_with_files(0, 0).to_checksums(), "");
+    }
+
+    #[test]
+    fn test_content_index_and_shared_chunks() {
+        // File 1 of `a` repeats the first chunk of file 0.
+        let a = Manifest::new(
+            CURRENT_STATE_SYNC_VERSION,
+            file_table(&[300, 100]),
+            vec![
+                chunk(0, 0, 100, 1),
+                chunk(0, 100, 100, 2),
+                chunk(0, 200, 100, 3),
+                chunk(1, 0, 100, 1),
+            ],
+        );
+        let b = Manifest::new(
+            CURRENT_STATE_SYNC_VERSION,
+            file_table(&[100, 200]),
+            vec![
+                chunk(0, 0, 100, 4),
+                chunk(1, 0, 100, 3),
+                chunk(1, 100, 100, 1),
+            ],
+        );
+
+        let index = a.content_index();
+        assert_eq!(index.len(), 3);
+        assert_eq!(index[&[1; 32]], (0, 0..100));
+        assert_eq!(index[&[2; 32]], (0, 100..200));
+        assert_eq!(index[&[3; 32]], (0, 200..300));
+        assert_eq!(
+            index.keys().cloned().collect::<HashSet<_>>(),
+            a.chunk_hash_set()
+        );
+        assert_eq!(b.content_index()[&[1; 32]], (1, 100..200));
+
+        assert_eq!(shared_chunks(&a, &b), vec![[1; 32], [3; 32]]);
+        assert_eq!(shared_chunks(&b, &a), vec![[1; 32], [3; 32]]);
+        assert_eq!(shared_chunks(&a, &a), vec![[1; 32], [2; 32], [3; 32]]);
+
+        let empty = manifest_with_files(0, 0);
+        assert!(empty.content_index().is_empty());
+        assert!(shared_chunks(&a, &empty).is_empty());
+    }
+
+    #[test]
+    fn test_chunk_index_bounds() {
+        // File 0 has two chunks, file 1 is empty, file 2 has three chunks and
+        // file 3 a single one.
+        let manifest = Manifest::new(
+            CURRENT_STATE_SYNC_VERSION,
+            file_table(&[200, 0, 300, 100]),
+            vec![
+                chunk(0, 0, 100, 0),
+                chunk(0, 100, 100, 0),
+                chunk(2, 0, 100, 0),
+                chunk(2, 100, 100, 0),
+                chunk(2, 200, 100, 0),
+                chunk(3, 0, 100, 0),
+            ],
+        );
+
+        assert_eq!(manifest.chunk_index_bounds(0), Some((0, 2)));
+        assert_eq!(manifest.chunk_index_bounds(1), Some((2, 2)));
+        assert_eq!(manifest.chunk_index_bounds(2), Some((2, 5)));
+        assert_eq!(manifest.chunk_index_bounds(3), Some((5, 6)));
+        assert_eq!(manifest.chunk_index_bounds(4), None);
+        assert_eq!(manifest.chunk_index_bounds(u32::MAX), None);
+
+        let num_chunks: Vec<_> = (0..5).map(|i| manifest.num_chunks_for_file(i)).collect();
+        assert_eq!(num_chunks, vec![2, 0, 3, 1, 0]);
+        assert_eq!(num_chunks.iter().sum::<usize>(), manifest.chunk_table.len());
+
+        for file_index in 0..4 {
+            let (start, end) = manifest.chunk_index_bounds(file_index).unwrap();
+            assert!(manifest.chunk_table[start..end]
+                .iter()
+                .all(|c| c.file_index == file_index));
+        }
+
+        let empty = manifest_with_files(0, 0);
+        assert_eq!(empty.chunk_index_bounds(0), None);
+        assert_eq!(empty.num_chunks_for_file(0), 0);
+    }
+
+    #[test]
+    fn test_chunks_covering() {
+        // File 0 has a single chunk, file 1 three chunks (the last one short)
+        // and file 2 is empty.
+        let manifest = Manifest::new(
+            CURRENT_STATE_SYNC_VERSION,
+            file_table(&[100, 250, 0]),
+            vec![
+                chunk(0, 0, 100, 0),
+                chunk(1, 0, 100, 0),
+                chunk(1, 100, 100, 0),
+                chunk(1, 200, 50, 0),
+            ],
+        );
+        assert_eq!(manifest.chunk_table[3].end_offset(), 250);
+
+        // Within one chunk, including its first and last byte.
+        assert_eq!(manifest.chunks_covering(1, 0..1), vec![1]);
+        assert_eq!(manifest.chunks_covering(1, 99..100), vec![1]);
+        assert_eq!(manifest.chunks_covering(1, 100..200), vec![2]);
+        // Spanning two chunks.
+        assert_eq!(manifest.chunks_covering(1, 99..101), vec![1, 2]);
+        assert_eq!(manifest.chunks_covering(1, 150..220), vec![2, 3]);
+        // All chunks, and past the end of the file.
+        assert_eq!(manifest.chunks_covering(1, 0..250), vec![1, 2, 3]);
+        assert_eq!(manifest.chunks_covering(1, 0..u64::MAX), vec![1, 2, 3]);
+        assert_eq!(manifest.chunks_covering(1, 240..1000), vec![3]);
+        assert!(manifest.chunks_covering(1, 250..1000).is_empty());
+        // Empty ranges.
+        assert!(manifest.chunks_covering(1, 50..50).is_empty());
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 150..50;
+        assert!(manifest.chunks_covering(1, reversed).is_empty());
+        // Other files.
+        assert_eq!(manifest.chunks_covering(0, 0..1000), vec![0]);
+        assert!(manifest.chunks_covering(2, 0..1000).is_empty());
+        assert!(manifest.chunks_covering(3, 0..1000).is_empty());
+    }
+
+    #[test]
+    fn test_manifest_into_parts_and_from_parts() {
+        let manifest = manifest_with_files(3, 2);
+        let expected = manifest_with_files(3, 2);
+
+        // A uniquely held manifest gives up its tables without copying them.
+        assert_eq!(Arc::strong_count(&manifest.0), 1);
+        let file_table_ptr = manifest.file_table.as_ptr();
+        let chunk_table_ptr = manifest.chunk_table.as_ptr();
+        let (version, file_table, chunk_table) = manifest.into_parts();
+        assert_eq!(file_table.as_ptr(), file_table_ptr);
+        assert_eq!(chunk_table.as_ptr(), chunk_table_ptr);
+
+        let manifest = Manifest::from_parts(version, file_table, chunk_table);
+        assert_eq!(manifest, expected);
+
+        // A shared manifest is cloned and the other reference stays valid.
+        let shared = manifest.clone();
+        assert_eq!(Arc::strong_count(&manifest.0), 2);
+        let (version, file_table, chunk_table) = manifest.into_parts();
+        assert_eq!(Arc::strong_count(&shared.0), 1);
+        assert_ne!(file_table.as_ptr(), shared.file_table.as_ptr());
+        assert_eq!(shared, expected);
+        assert_eq!(
+            Manifest::from_parts(version, file_table, chunk_table),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_approx_heap_size_grows_linearly() {
+        assert_eq!(manifest_with_files(0, 0).approx_heap_size(), 0);
+
+        let per_file = std::mem::size_of::<FileInfo>() + 2 * std::mem::size_of::<ChunkInfo>();
+        let mut previous = 0;
+        for num_files in [1, 10, 100, 1000] {
+            let size = manifest_with_files(num_files, 2).approx_heap_size();
+            assert!(size > previous);
+            assert!(size >= num_files as usize * per_file);
+            // Paths are short, so they should not dominate the estimate.
+            assert!(size <= 2 * num_files as usize * per_file);
+            previous = size;
+        }
+
+        // The metadata label is counted.
+        let label = "a".repeat(1000);
+        let with_metadata = manifest_with_files(10, 2).with_metadata(Some(ManifestMetadata {
+            checkpoint_height: 1,
+            label: label.clone(),
+        }));
+        assert_eq!(
+            with_metadata.approx_heap_size(),
+            manifest_with_files(10, 2).approx_heap_size() + label.capacity()
+        );
+    }
+
+    #[test]
+    fn test_file_group_chunks_indices() {
+        let empty = FileGroupChunks::default();
+        assert_eq!(empty.total_indices(), 0);
+        assert_eq!(empty.indices_in(&FILE_GROUP_CHUNK_ID_OFFSET), 0);
+
+        let file_group_chunks = FileGroupChunks::new(BTreeMap::from([
+            (FILE_GROUP_CHUNK_ID_OFFSET, vec![1, 2, 3]),
+            (FILE_GROUP_CHUNK_ID_OFFSET + 1, vec![4]),
+            (FILE_GROUP_CHUNK_ID_OFFSET + 2, vec![]),
+        ]));
+        assert_eq!(file_group_chunks.total_indices(), 4);
+        assert_eq!(file_group_chunks.indices_in(&FILE_GROUP_CHUNK_ID_OFFSET), 3);
+        assert_eq!(
+            file_group_chunks.indices_in(&(FILE_GROUP_CHUNK_ID_OFFSET + 1)),
+            1
+        );
+        assert_eq!(
+            file_group_chunks.indices_in(&(FILE_GROUP_CHUNK_ID_OFFSET + 2)),
+            0
+        );
+        assert_eq!(
+            file_group_chunks.indices_in(&(FILE_GROUP_CHUNK_ID_OFFSET + 3)),
+            0
+        );
+    }
+
+    #[test]
+    fn test_coverage_gaps() {
+        // Fully covered, including an empty file without chunks.
+        let manifest = Manifest::new(
+            CURRENT_STATE_SYNC_VERSION,
+            file_table(&[250, 0, 100]),
+            vec![
+                chunk(0, 0, 100, 0),
+                chunk(0, 100, 100, 0),
+                chunk(0, 200, 50, 0),
+                chunk(2, 0, 100, 0),
+            ],
+        );
+        assert!(manifest.coverage_gaps().is_empty());
+        assert!(manifest_with_files(3, 2).coverage_gaps().is_empty());
+
+        // File 0 lacks its last chunk, file 1 has no chunks at all and the
+        // chunk of file 2 is larger than the file.
+        let manifest = Manifest::new(
+            CURRENT_STATE_SYNC_VERSION,
+            file_table(&[250, 10, 100, 100]),
+            vec![
+                chunk(0, 0, 100, 0),
+                chunk(0, 100, 100, 0),
+                chunk(2, 0, 130, 0),
+                chunk(3, 0, 100, 0),
+            ],
+        );
+        assert_eq!(manifest.coverage_gaps(), vec![(0, 50), (1, 10), (2, 30)]);
+    }
+
+    #[test]
+    fn test_sync_progress() {
+        let manifest = Manifest::new(
+            CURRENT_STATE_SYNC_VERSION,
+            file_table(&[1_000, 100, 200, 300]),
+            vec![
+                chunk(0, 0, 1_000, 0),
+                chunk(1, 0, 100, 0),
+                chunk(2, 0, 200, 0),
+                chunk(3, 0, 300, 0),
+            ],
+        );
+        let file_group_id = FILE_GROUP_CHUNK_ID_OFFSET;
+        let file_group_chunks =
+            FileGroupChunks::new(BTreeMap::from([(file_group_id, vec![1, 2, 3])]));
+        let file_chunk_id = |chunk_index: u32| chunk_index + FILE_CHUNK_ID_OFFSET as u32;
+
+        let mut progress = SyncProgress::new(&manifest, file_group_chunks);
+        assert!(!progress.is_complete());
+        assert_eq!(progress.bytes_fetched(), 0);
+        assert_eq!(progress.percent_complete(), 0.0);
+
+        // Manifest chunks and unknown chunks do not count.
+        progress.mark_fetched(0);
+        progress.mark_fetched(MANIFEST_CHUNK_ID_OFFSET);
+        progress.mark_fetched(file_chunk_id(4));
+        progress.mark_fetched(file_group_id + 1);
+        assert_eq!(progress.bytes_fetched(), 0);
+
+        // The file group chunk advances the progress by the sizes of its members.
+        progress.mark_fetched(file_group_id);
+        assert_eq!(progress.bytes_fetched(), 600);
+        assert_eq!(progress.percent_complete(), 37.5);
+        assert!(!progress.is_complete());
+
+        // Marking chunks again has no effect.
+        progress.mark_fetched(file_group_id);
+        progress.mark_fetched(file_chunk_id(2));
+        assert_eq!(progress.bytes_fetched(), 600);
+
+        progress.mark_fetched(file_chunk_id(0));
+        assert!(progress.is_complete());
+        assert_eq!(progress.bytes_fetched(), 1_600);
+        assert_eq!(progress.percent_complete(), 100.0);
+
+        // Marking all chunks individually also completes the sync.
+        let mut progress = SyncProgress::new(&manifest, FileGroupChunks::default());
+        for chunk_index in 0..4 {
+            assert!(!progress.is_complete());
+            progress.mark_fetched(file_chunk_id(chunk_index));
+        }
+        assert!(progress.is_complete());
+        assert_eq!(progress.percent_complete(), 100.0);
+
+        let empty = SyncProgress::new(&manifest_with_files(0, 0), FileGroupChunks::default());
+        assert!(empty.is_complete());
+        assert_eq!(empty.percent_complete(), 100.0);
+    }
+
+    #[test]
+    fn test_decode_meta_manifest_checked() {
+        let meta_manifest = MetaManifest {
+            version: StateSyncVersion::V2,
+            sub_manifest_hashes: vec![[1; 32], [2; 32]],
+        };
+        let bytes = encode_meta_manifest(&meta_manifest);
+        assert_eq!(
+            decode_meta_manifest_checked(&bytes, StateSyncVersion::V2),
+            Ok(meta_manifest.clone())
+        );
+        assert_eq!(
+            decode_meta_manifest_checked(&bytes, MAX_SUPPORTED_STATE_SYNC_VERSION),
+            Ok(meta_manifest)
+        );
+
+        let future_version = MetaManifest {
+            version: StateSyncVersion::V3,
+            sub_manifest_hashes: vec![[1; 32]],
+        };
+        let bytes = encode_meta_manifest(&future_version);
+        assert!(decode_meta_manifest(&bytes).is_ok());
+        let err = decode_meta_manifest_checked(&bytes, StateSyncVersion::V2).unwrap_err();
+        assert!(err.contains("version V3 is not supported"), "{}", err);
+
+        let unknown_version = pb::MetaManifest {
+            version: 1000,
+            sub_manifest_hashes: vec![vec![1; 32]],
+        };
+        let bytes = prost::Message::encode_to_vec(&unknown_version);
+        assert!(decode_meta_manifest_checked(&bytes, MAX_SUPPORTED_STATE_SYNC_VERSION).is_err());
+
+        let no_sub_manifests = MetaManifest {
+            version: StateSyncVersion::V2,
+            sub_manifest_hashes: vec![],
+        };
+        let bytes = encode_meta_manifest(&no_sub_manifests);
+        assert!(decode_meta_manifest(&bytes).is_ok());
+        let err = decode_meta_manifest_checked(&bytes, StateSyncVersion::V2).unwrap_err();
+        assert!(err.contains("sub-manifest"), "{}", err);
+    }
+
+    #[test]
+    fn test_decode_manifest_checked() {
+        let manifest = manifest_with_files(2, 3);
+        let bytes = encode_manifest(&manifest);
+        assert_eq!(decode_manifest_checked(&bytes), Ok(manifest.clone()));
+
+        for version in [99, MAX_SUPPORTED_STATE_SYNC_VERSION as u32 + 1] {
+            let mut unsupported = pb::Manifest::from(manifest.clone());
+            unsupported.version = version;
+            let bytes = prost::Message::encode_to_vec(&unsupported);
+            let err = decode_manifest_checked(&bytes).unwrap_err();
+            assert!(
+                err.contains(&format!("manifest version {} is not supported", version)),
+                "{}",
+                err
+            );
+        }
+
+        assert!(decode_manifest_checked(&[0xff, 0x00, 0x13]).is_err());
+    }
+
     #[test]
     fn test_state_sync_chunk_type() {
         assert_eq!(state_sync_chunk_type(0), StateSyncChunk::MetaManifestChunk);
@@ -491,4 +2118,327 @@ mod tests {
                 )
             });
     }
+
+    #[test]
+    fn test_state_sync_chunk_category_label() {
+        assert_eq!(
+            StateSyncChunk::MetaManifestChunk.category_label(),
+            "meta_manifest"
+        );
+        assert_eq!(StateSyncChunk::FileChunk(7).category_label(), "file");
+        assert_eq!(
+            StateSyncChunk::FileGroupChunk(FILE_GROUP_CHUNK_ID_OFFSET).category_label(),
+            "file_group"
+        );
+        assert_eq!(
+            StateSyncChunk::ManifestChunk(3).category_label(),
+            "manifest"
+        );
+
+        // Every chunk ID maps to one of the labels, and each label is used.
+        let labels: HashSet<_> = [
+            0,
+            1,
+            FILE_GROUP_CHUNK_ID_OFFSET,
+            MANIFEST_CHUNK_ID_OFFSET,
+            u32::MAX,
+        ]
+        .into_iter()
+        .map(|chunk_id| state_sync_chunk_type(chunk_id).category_label())
+        .collect();
+        assert_eq!(
+            labels,
+            StateSyncChunk::CATEGORY_LABELS.into_iter().collect()
+        );
+        assert_eq!(
+            StateSyncChunk::CATEGORY_LABELS,
+            ["meta_manifest", "file", "file_group", "manifest"]
+        );
+    }
+
+    #[test]
+    fn test_meta_manifest_chunk_ids() {
+        let meta_manifest = MetaManifest {
+            version: CURRENT_STATE_SYNC_VERSION,
+            sub_manifest_hashes: vec![[0; 32], [1; 32], [2; 32]],
+        };
+        assert_eq!(meta_manifest.num_chunks(), 3);
+
+        let chunk_ids: Vec<u32> = meta_manifest.chunk_ids().collect();
+        assert_eq!(
+            chunk_ids,
+            vec![
+                MANIFEST_CHUNK_ID_OFFSET,
+                MANIFEST_CHUNK_ID_OFFSET + 1,
+                MANIFEST_CHUNK_ID_OFFSET + 2
+            ]
+        );
+        for (i, chunk_id) in chunk_ids.into_iter().enumerate() {
+            assert_eq!(
+                state_sync_chunk_type(chunk_id),
+                StateSyncChunk::ManifestChunk(i as u32)
+            );
+        }
+
+        let empty = MetaManifest {
+            version: CURRENT_STATE_SYNC_VERSION,
+            sub_manifest_hashes: vec![],
+        };
+        assert_eq!(empty.num_chunks(), 0);
+        assert_eq!(empty.chunk_ids().count(), 0);
+    }
+
+    #[test]
+    fn test_state_sync_chunk_display_and_parse() {
+        let boundary_ids = [
+            0,
+            1,
+            2,
+            FILE_GROUP_CHUNK_ID_OFFSET - 1,
+            FILE_GROUP_CHUNK_ID_OFFSET,
+            FILE_GROUP_CHUNK_ID_OFFSET + 1,
+            MANIFEST_CHUNK_ID_OFFSET - 1,
+            MANIFEST_CHUNK_ID_OFFSET,
+            MANIFEST_CHUNK_ID_OFFSET + 1,
+            u32::MAX,
+        ];
+        for chunk_id in boundary_ids {
+            let chunk = state_sync_chunk_type(chunk_id);
+            assert_eq!(chunk.chunk_id(), Some(chunk_id));
+
+            let displayed = chunk.to_string();
+            assert_eq!(displayed.parse::<StateSyncChunk>(), Ok(chunk));
+            // Raw chunk IDs are accepted as well.
+            assert_eq!(
+                chunk_id.to_string().parse::<StateSyncChunk>(),
+                Ok(state_sync_chunk_type(chunk_id))
+            );
+        }
+
+        assert_eq!(
+            StateSyncChunk::MetaManifestChunk.to_string(),
+            "MetaManifestChunk"
+        );
+        assert_eq!(StateSyncChunk::FileChunk(42).to_string(), "FileChunk(42)");
+        assert_eq!(
+            StateSyncChunk::FileGroupChunk(FILE_GROUP_CHUNK_ID_OFFSET).to_string(),
+            format!("FileGroupChunk({})", FILE_GROUP_CHUNK_ID_OFFSET)
+        );
+        assert_eq!(
+            StateSyncChunk::ManifestChunk(3).to_string(),
+            "ManifestChunk(3)"
+        );
+        assert_eq!(
+            " ManifestChunk(3) ".parse::<StateSyncChunk>(),
+            Ok(StateSyncChunk::ManifestChunk(3))
+        );
+
+        // Indices outside of the range of their chunk type.
+        for s in [
+            format!("FileChunk({})", FILE_GROUP_CHUNK_ID_OFFSET - 1),
+            format!("FileGroupChunk({})", FILE_GROUP_CHUNK_ID_OFFSET - 1),
+            format!("FileGroupChunk({})", MANIFEST_CHUNK_ID_OFFSET),
+            format!("ManifestChunk({})", u32::MAX - MANIFEST_CHUNK_ID_OFFSET + 1),
+        ] {
+            assert!(s.parse::<StateSyncChunk>().is_err(), "{}", s);
+        }
+
+        // Malformed strings.
+        for s in [
+            "",
+            "FileChunk",
+            "FileChunk()",
+            "FileChunk(-1)",
+            "FileChunk(1",
+            "OtherChunk(1)",
+            "MetaManifestChunk(0)",
+            "4294967296",
+        ] {
+            assert!(s.parse::<StateSyncChunk>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_compact_string() {
+        let mut hash = [0xff; 32];
+        hash[..8].copy_from_slice(&[0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef]);
+
+        let file_info = FileInfo {
+            relative_path: "subdir/memory".into(),
+            size_bytes: 2048,
+            hash,
+        };
+        assert_eq!(
+            file_info.to_compact_string(),
+            "subdir/memory size=2048 hash=0123456789abcdef"
+        );
+
+        let chunk_info = ChunkInfo {
+            file_index: 3,
+            size_bytes: 1024,
+            offset: 1 << 20,
+            hash,
+        };
+        assert_eq!(
+            chunk_info.to_compact_string(),
+            "file=3 offset=1048576 size=1024 hash=0123456789abcdef"
+        );
+    }
+
+    #[test]
+    fn test_is_canonically_ordered() {
+        let manifest = manifest_with_files(3, 2);
+        assert!(manifest.is_canonically_ordered());
+        assert!(Manifest::new(StateSyncVersion::V3, vec![], vec![]).is_canonically_ordered());
+
+        let (version, mut file_table, chunk_table) = manifest.clone().into_parts();
+        file_table.swap(0, 2);
+        assert!(!Manifest::new(version, file_table, chunk_table).is_canonically_ordered());
+
+        let (version, file_table, mut chunk_table) = manifest.clone().into_parts();
+        chunk_table.swap(0, 1);
+        assert!(!Manifest::new(version, file_table, chunk_table).is_canonically_ordered());
+
+        let (version, file_table, mut chunk_table) = manifest.into_parts();
+        chunk_table.swap(1, 2);
+        assert!(!Manifest::new(version, file_table, chunk_table).is_canonically_ordered());
+    }
+
+    #[test]
+    fn test_state_sync_version_migrations() {
+        use strum::IntoEnumIterator;
+
+        for version in StateSyncVersion::iter() {
+            assert!(StateSyncVersion::can_migrate(version, version));
+            assert_eq!(
+                StateSyncVersion::migration_note(version, version),
+                Some("no changes")
+            );
+        }
+
+        for (from, to) in StateSyncVersion::iter().zip(StateSyncVersion::iter().skip(1)) {
+            assert!(
+                StateSyncVersion::can_migrate(from, to),
+                "{} -> {}",
+                from,
+                to
+            );
+            assert!(
+                !StateSyncVersion::can_migrate(to, from),
+                "{} -> {}",
+                to,
+                from
+            );
+            assert!(
+                StateSyncVersion::migration_note(from, to).is_some(),
+                "{} -> {}",
+                from,
+                to
+            );
+            assert_eq!(StateSyncVersion::migration_note(to, from), None);
+        }
+
+        assert!(!StateSyncVersion::can_migrate(
+            StateSyncVersion::V1,
+            StateSyncVersion::V3
+        ));
+        assert_eq!(
+            StateSyncVersion::migration_note(StateSyncVersion::V1, StateSyncVersion::V3),
+            None
+        );
+        assert_eq!(
+            StateSyncVersion::migration_note(StateSyncVersion::V2, StateSyncVersion::V3),
+            Some("the file index is no longer included in file hashes")
+        );
+    }
+
+    #[test]
+    fn test_hashes_include_file_index() {
+        use strum::IntoEnumIterator;
+
+        for version in StateSyncVersion::iter() {
+            let expected = match version {
+                StateSyncVersion::V0 | StateSyncVersion::V1 | StateSyncVersion::V2 => true,
+                StateSyncVersion::V3 => false,
+            };
+            assert_eq!(version.hashes_include_file_index(), expected, "{}", version);
+        }
+    }
+
+    #[test]
+    fn test_small_file_indices() {
+        let manifest = Manifest::new(
+            StateSyncVersion::V3,
+            file_table(&[100, 8192, 8193, 0, 1 << 20]),
+            vec![],
+        );
+
+        assert_eq!(manifest.small_file_indices(8192), vec![0, 1, 3]);
+        assert!(manifest.is_groupable(1, 8192));
+        assert!(!manifest.is_groupable(2, 8192));
+
+        assert_eq!(manifest.small_file_indices(8193), vec![0, 1, 2, 3]);
+        assert!(manifest.is_groupable(2, 8193));
+
+        assert_eq!(manifest.small_file_indices(0), vec![3]);
+        assert!(!manifest.is_groupable(0, 0));
+        assert!(manifest.is_groupable(3, 0));
+
+        assert!(!manifest.is_groupable(5, u64::MAX));
+    }
+
+    #[test]
+    fn test_partition_chunks_by_hash() {
+        let chunk_table: Vec<ChunkInfo> = (0..=255u8)
+            .map(|i| {
+                let mut hash = [i.wrapping_mul(37); 32];
+                hash[1] = i;
+                ChunkInfo {
+                    file_index: 0,
+                    size_bytes: 1,
+                    offset: i as u64,
+                    hash,
+                }
+            })
+            .collect();
+        let manifest = Manifest::new(StateSyncVersion::V3, vec![], chunk_table);
+
+        for shards in [1, 2, 3, 7, 16] {
+            let partition = manifest.partition_chunks_by_hash(shards);
+            assert_eq!(partition.len(), shards);
+
+            let mut all: Vec<usize> = partition.iter().flatten().cloned().collect();
+            all.sort_unstable();
+            assert_eq!(all, (0..256).collect::<Vec<_>>());
+        }
+
+        // With a power of two number of shards, the shard boundaries fall on
+        // values of the first byte.
+        for shards in [1, 2, 4, 16] {
+            let partition = manifest.partition_chunks_by_hash(shards);
+            for (shard, chunk_indices) in partition.iter().enumerate() {
+                let mut start = [0; 32];
+                start[0] = (shard * 256 / shards) as u8;
+                let mut end = [0xff; 32];
+                if shard + 1 < shards {
+                    end = [0; 32];
+                    end[0] = ((shard + 1) * 256 / shards) as u8;
+                }
+                assert_eq!(chunk_indices, &manifest.chunks_in_hash_range(start, end));
+            }
+        }
+        assert!(manifest.partition_chunks_by_hash(0).is_empty());
+
+        let mut start = [0u8; 32];
+        start[0] = 37;
+        let mut end = start;
+        end[1] = 2;
+        let chunks = manifest.chunks_in_hash_range(start, end);
+        assert_eq!(chunks, vec![1]);
+        assert!(manifest.chunks_in_hash_range(end, start).is_empty());
+        assert_eq!(
+            manifest.chunks_in_hash_range([0; 32], [0xff; 32]).len(),
+            256
+        );
+    }
 }
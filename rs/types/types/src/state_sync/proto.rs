@@ -1,10 +1,60 @@
 //! Conversions from Rust to proto structs and back for `StateSync`.
-use crate::state_sync::{ChunkInfo, FileInfo, Manifest, MetaManifest};
+use crate::state_sync::{ChunkInfo, FileInfo, Manifest, ManifestMetadata, MetaManifest};
 use ic_protobuf::proxy::try_decode_hash;
 use ic_protobuf::proxy::ProxyDecodeError;
 use ic_protobuf::state::sync::v1 as pb;
 use std::convert::TryFrom;
 
+// Field numbers of `pb::Manifest`, for the code that encodes, measures or
+// decodes a manifest one table entry at a time.
+pub(crate) const MANIFEST_VERSION_TAG: u32 = 1;
+pub(crate) const MANIFEST_FILE_TABLE_TAG: u32 = 2;
+pub(crate) const MANIFEST_CHUNK_TABLE_TAG: u32 = 3;
+pub(crate) const MANIFEST_METADATA_TAG: u32 = 4;
+
+/// Returns the length of a length-delimited field with the given tag and
+/// payload length.
+pub(crate) fn length_delimited_encoded_len(tag: u32, len: usize) -> usize {
+    use prost::encoding::{encoded_len_varint, key_len};
+    key_len(tag) + encoded_len_varint(len as u64) + len
+}
+
+/// Returns the length of the encoding of `file_info` as a `pb::FileInfo`,
+/// computed from the borrowed fields without converting it.
+pub(crate) fn file_info_encoded_len(file_info: &FileInfo) -> usize {
+    use prost::encoding::uint64;
+
+    // Like prost, omit the fields that have the default value.
+    let relative_path_len = file_info.relative_path.to_string_lossy().len();
+    let mut len = 0;
+    if relative_path_len != 0 {
+        len += length_delimited_encoded_len(1, relative_path_len);
+    }
+    if file_info.size_bytes != 0 {
+        len += uint64::encoded_len(2, &file_info.size_bytes);
+    }
+    len + length_delimited_encoded_len(3, file_info.hash.len())
+}
+
+/// Returns the length of the encoding of `chunk_info` as a `pb::ChunkInfo`,
+/// computed from the borrowed fields without converting it.
+pub(crate) fn chunk_info_encoded_len(chunk_info: &ChunkInfo) -> usize {
+    use prost::encoding::{uint32, uint64};
+
+    // Like prost, omit the fields that have the default value.
+    let mut len = 0;
+    if chunk_info.file_index != 0 {
+        len += uint32::encoded_len(1, &chunk_info.file_index);
+    }
+    if chunk_info.size_bytes != 0 {
+        len += uint32::encoded_len(2, &chunk_info.size_bytes);
+    }
+    if chunk_info.offset != 0 {
+        len += uint64::encoded_len(3, &chunk_info.offset);
+    }
+    len + length_delimited_encoded_len(4, chunk_info.hash.len())
+}
+
 impl From<FileInfo> for pb::FileInfo {
     fn from(file_info: FileInfo) -> Self {
         Self {
@@ -26,6 +76,15 @@ impl From<ChunkInfo> for pb::ChunkInfo {
     }
 }
 
+impl From<ManifestMetadata> for pb::ManifestMetadata {
+    fn from(metadata: ManifestMetadata) -> Self {
+        Self {
+            checkpoint_height: metadata.checkpoint_height,
+            label: metadata.label,
+        }
+    }
+}
+
 impl From<Manifest> for pb::Manifest {
     fn from(manifest: Manifest) -> Self {
         Self {
@@ -42,6 +101,7 @@ impl From<Manifest> for pb::Manifest {
                 .cloned()
                 .map(|entry| entry.into())
                 .collect(),
+            metadata: manifest.metadata.clone().map(|metadata| metadata.into()),
         }
     }
 }
@@ -84,10 +144,20 @@ impl TryFrom<pb::ChunkInfo> for ChunkInfo {
     }
 }
 
+impl From<pb::ManifestMetadata> for ManifestMetadata {
+    fn from(metadata: pb::ManifestMetadata) -> Self {
+        Self {
+            checkpoint_height: metadata.checkpoint_height,
+            label: metadata.label,
+        }
+    }
+}
+
 impl TryFrom<pb::Manifest> for Manifest {
     type Error = ProxyDecodeError;
 
     fn try_from(manifest: pb::Manifest) -> Result<Self, ProxyDecodeError> {
+        let metadata = manifest.metadata.map(ManifestMetadata::from);
         Ok(Self::new(
             manifest
                 .version
@@ -103,7 +173,8 @@ impl TryFrom<pb::Manifest> for Manifest {
                 .into_iter()
                 .map(ChunkInfo::try_from)
                 .collect::<Result<_, _>>()?,
-        ))
+        )
+        .with_metadata(metadata))
     }
 }
 
@@ -0,0 +1,278 @@
+//! Incremental decoding of manifests encoded with `encode_manifest`.
+use crate::state_sync::proto::{
+    MANIFEST_CHUNK_TABLE_TAG, MANIFEST_FILE_TABLE_TAG, MANIFEST_VERSION_TAG,
+};
+use crate::state_sync::{ChunkInfo, FileInfo, StateSyncVersion};
+use ic_protobuf::state::sync::v1 as pb;
+use prost::Message;
+use std::convert::TryFrom;
+use std::io::Read;
+
+// Protobuf wire types.
+const WIRE_TYPE_VARINT: u32 = 0;
+const WIRE_TYPE_64_BIT: u32 = 1;
+const WIRE_TYPE_LENGTH_DELIMITED: u32 = 2;
+const WIRE_TYPE_32_BIT: u32 = 5;
+
+/// Decodes a manifest from a reader without holding its chunk table in memory.
+///
+/// `ManifestReader::new` decodes the version and the file table, and `chunks`
+/// then decodes the chunk table one entry at a time, so a consumer processing
+/// the entries one by one never holds the whole chunk table. The entries are
+/// the same, and in the same order, as those of the manifest returned by
+/// `decode_manifest`.
+///
+/// Unlike `decode_manifest`, this requires the fields to appear in field
+/// number order, which is the order in which `encode_manifest` writes them.
+/// Fields other than the version and the tables are skipped.
+///
+/// The input is read in small pieces, so `reader` should be buffered.
+pub struct ManifestReader<R> {
+    reader: R,
+    version: StateSyncVersion,
+    file_table: Vec<FileInfo>,
+    /// The first chunk table entry, read while decoding the file table.
+    first_chunk: Option<Vec<u8>>,
+    done: bool,
+}
+
+impl<R: Read> ManifestReader<R> {
+    /// Decodes the version and the file table of the manifest read from
+    /// `reader`, stopping at the first chunk table entry.
+    pub fn new(reader: R) -> Result<Self, String> {
+        let mut manifest_reader = Self {
+            reader,
+            version: StateSyncVersion::V0,
+            file_table: Vec::new(),
+            first_chunk: None,
+            done: false,
+        };
+
+        let mut version = 0;
+        while let Some((field, wire_type)) = manifest_reader.read_key()? {
+            match (field, wire_type) {
+                (MANIFEST_VERSION_TAG, WIRE_TYPE_VARINT) => {
+                    version = manifest_reader.read_varint()? as u32;
+                }
+                (MANIFEST_FILE_TABLE_TAG, WIRE_TYPE_LENGTH_DELIMITED) => {
+                    let bytes = manifest_reader.read_length_delimited()?;
+                    manifest_reader.file_table.push(decode_file_info(&bytes)?);
+                }
+                (MANIFEST_CHUNK_TABLE_TAG, WIRE_TYPE_LENGTH_DELIMITED) => {
+                    manifest_reader.first_chunk = Some(manifest_reader.read_length_delimited()?);
+                    break;
+                }
+                (MANIFEST_VERSION_TAG, _)
+                | (MANIFEST_FILE_TABLE_TAG, _)
+                | (MANIFEST_CHUNK_TABLE_TAG, _) => {
+                    return Err(format!(
+                        "failed to decode Manifest: field {} has wire type {}",
+                        field, wire_type
+                    ));
+                }
+                _ => manifest_reader.skip(wire_type)?,
+            }
+        }
+        manifest_reader.done = manifest_reader.first_chunk.is_none();
+        manifest_reader.version = StateSyncVersion::try_from(version)
+            .map_err(|v| format!("failed to decode Manifest: unknown version {}", v))?;
+
+        Ok(manifest_reader)
+    }
+
+    /// Returns the version of the manifest.
+    pub fn version(&self) -> StateSyncVersion {
+        self.version
+    }
+
+    /// Returns the file table of the manifest.
+    pub fn file_table(&self) -> &[FileInfo] {
+        &self.file_table
+    }
+
+    /// Returns an iterator decoding the chunk table entries of the manifest,
+    /// in order.
+    ///
+    /// The iterator stops after returning the first error.
+    pub fn chunks(&mut self) -> impl Iterator<Item = Result<ChunkInfo, String>> + '_ {
+        std::iter::from_fn(move || {
+            if self.done {
+                return None;
+            }
+            let next = self.next_chunk();
+            if !matches!(next, Ok(Some(_))) {
+                self.done = true;
+            }
+            next.transpose()
+        })
+    }
+
+    fn next_chunk(&mut self) -> Result<Option<ChunkInfo>, String> {
+        if let Some(bytes) = self.first_chunk.take() {
+            return decode_chunk_info(&bytes).map(Some);
+        }
+        while let Some((field, wire_type)) = self.read_key()? {
+            match (field, wire_type) {
+                (MANIFEST_CHUNK_TABLE_TAG, WIRE_TYPE_LENGTH_DELIMITED) => {
+                    let bytes = self.read_length_delimited()?;
+                    return decode_chunk_info(&bytes).map(Some);
+                }
+                (MANIFEST_VERSION_TAG, _)
+                | (MANIFEST_FILE_TABLE_TAG, _)
+                | (MANIFEST_CHUNK_TABLE_TAG, _) => {
+                    return Err(format!(
+                        "failed to decode Manifest: unexpected field {} with wire type {} \
+                         in the chunk table",
+                        field, wire_type
+                    ));
+                }
+                _ => self.skip(wire_type)?,
+            }
+        }
+        Ok(None)
+    }
+
+    /// Reads the key of the next field, or returns `None` at the end of the
+    /// input.
+    fn read_key(&mut self) -> Result<Option<(u32, u32)>, String> {
+        let mut byte = [0u8; 1];
+        let n = self
+            .reader
+            .read(&mut byte)
+            .map_err(|err| format!("failed to read Manifest: {}", err))?;
+        if n == 0 {
+            return Ok(None);
+        }
+        let key = self.read_varint_from(byte[0])?;
+        let field = key >> 3;
+        if field == 0 || field > u32::MAX as u64 {
+            return Err(format!("failed to decode Manifest: invalid key {}", key));
+        }
+        Ok(Some((field as u32, (key & 0x7) as u32)))
+    }
+
+    fn read_byte(&mut self) -> Result<u8, String> {
+        let mut byte = [0u8; 1];
+        self.reader
+            .read_exact(&mut byte)
+            .map_err(|err| format!("failed to read Manifest: {}", err))?;
+        Ok(byte[0])
+    }
+
+    fn read_varint(&mut self) -> Result<u64, String> {
+        let first = self.read_byte()?;
+        self.read_varint_from(first)
+    }
+
+    /// Reads the rest of a varint whose first byte is `first`.
+    fn read_varint_from(&mut self, first: u8) -> Result<u64, String> {
+        let mut value = (first & 0x7f) as u64;
+        let mut byte = first;
+        let mut shift = 7;
+        while byte & 0x80 != 0 {
+            if shift >= 64 {
+                return Err("failed to decode Manifest: invalid varint".to_string());
+            }
+            byte = self.read_byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            shift += 7;
+        }
+        Ok(value)
+    }
+
+    fn read_length_delimited(&mut self) -> Result<Vec<u8>, String> {
+        let len = self.read_varint()?;
+        let mut bytes = Vec::new();
+        // Read through `take` rather than allocating `len` bytes upfront, so
+        // that a corrupted length does not cause a huge allocation.
+        (&mut self.reader)
+            .take(len)
+            .read_to_end(&mut bytes)
+            .map_err(|err| format!("failed to read Manifest: {}", err))?;
+        if bytes.len() as u64 != len {
+            return Err("failed to decode Manifest: unexpected end of input".to_string());
+        }
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, wire_type: u32) -> Result<(), String> {
+        let len = match wire_type {
+            WIRE_TYPE_VARINT => return self.read_varint().map(|_| ()),
+            WIRE_TYPE_64_BIT => 8,
+            WIRE_TYPE_LENGTH_DELIMITED => self.read_varint()?,
+            WIRE_TYPE_32_BIT => 4,
+            _ => {
+                return Err(format!(
+                    "failed to decode Manifest: unsupported wire type {}",
+                    wire_type
+                ))
+            }
+        };
+        let skipped = std::io::copy(&mut (&mut self.reader).take(len), &mut std::io::sink())
+            .map_err(|err| format!("failed to read Manifest: {}", err))?;
+        if skipped != len {
+            return Err("failed to decode Manifest: unexpected end of input".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn decode_file_info(bytes: &[u8]) -> Result<FileInfo, String> {
+    pb::FileInfo::decode(bytes)
+        .map_err(|err| err.to_string())
+        .and_then(|file_info| FileInfo::try_from(file_info).map_err(|err| err.to_string()))
+        .map_err(|err| format!("failed to decode FileInfo: {}", err))
+}
+
+fn decode_chunk_info(bytes: &[u8]) -> Result<ChunkInfo, String> {
+    pb::ChunkInfo::decode(bytes)
+        .map_err(|err| err.to_string())
+        .and_then(|chunk_info| ChunkInfo::try_from(chunk_info).map_err(|err| err.to_string()))
+        .map_err(|err| format!("failed to decode ChunkInfo: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_sync::tests::versioned_manifest_with_files as manifest;
+    use crate::state_sync::{decode_manifest, encode_manifest};
+
+    #[test]
+    fn test_manifest_reader_matches_decode_manifest() {
+        for manifest in [
+            manifest(StateSyncVersion::V0, 0, 0),
+            manifest(StateSyncVersion::V0, 2, 3),
+            manifest(StateSyncVersion::V2, 1, 0),
+            manifest(StateSyncVersion::V2, 3, 0),
+            manifest(StateSyncVersion::V2, 1, 1),
+            manifest(StateSyncVersion::V2, 10, 50),
+        ] {
+            let bytes = encode_manifest(&manifest);
+            let decoded = decode_manifest(&bytes).unwrap();
+
+            let mut reader = ManifestReader::new(&bytes[..]).unwrap();
+            assert_eq!(reader.version(), decoded.version);
+            assert_eq!(reader.file_table(), &decoded.file_table[..]);
+            let chunks: Vec<_> = reader.chunks().collect::<Result<_, _>>().unwrap();
+            assert_eq!(chunks, decoded.chunk_table);
+
+            // The chunk table is exhausted.
+            assert_eq!(reader.chunks().next(), None);
+        }
+    }
+
+    #[test]
+    fn test_manifest_reader_rejects_truncated_input() {
+        let bytes = encode_manifest(&manifest(StateSyncVersion::V2, 2, 3));
+
+        // Truncated in the last chunk table entry.
+        let mut reader = ManifestReader::new(&bytes[..bytes.len() - 1]).unwrap();
+        let chunks: Vec<_> = reader.chunks().collect();
+        assert_eq!(chunks.len(), 6);
+        assert!(chunks[..5].iter().all(|chunk| chunk.is_ok()));
+        assert!(chunks[5].is_err());
+
+        // Truncated in the file table.
+        assert!(ManifestReader::new(&bytes[..10]).is_err());
+    }
+}